@@ -1,4 +1,8 @@
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, SubCommand};
+
+use crate::management::cvss::Severity;
+use crate::management::crates_io::DependencyKind;
+use crate::utilities::terminal::input::AdvisoryQuery;
 
 pub mod management;
 pub mod utilities;
@@ -34,20 +38,261 @@ fn main() {
             .required(false)
             .help("Checks the dependencies of each of your dependencies, deepens search by 1 level")
         )
+        .arg(Arg::with_name("depth")
+            .long("depth")
+            .takes_value(true)
+            .required(false)
+            .validator(|value| value.parse::<usize>().map(|_| ()).map_err(|_| "must be a whole number of levels".to_string()))
+            .help("Checks the dependencies of your dependencies this many levels deep, using the resolved graph in Cargo.lock when there is one")
+        )
+        .arg(Arg::with_name("db-max-age")
+            .long("db-max-age")
+            .takes_value(true)
+            .required(false)
+            .default_value("24")
+            .validator(|value| value.parse::<u64>().map(|_| ()).map_err(|_| "must be a whole number of hours".to_string()))
+            .help("How many hours the cached advisory database is used for before it is downloaded again")
+        )
+        .arg(Arg::with_name("offline")
+            .long("offline")
+            .takes_value(false)
+            .required(false)
+            .help("Uses the cached advisory database without attempting to download a new copy")
+        )
+        .arg(Arg::with_name("advisory-db")
+            .long("advisory-db")
+            .takes_value(true)
+            .required(false)
+            .help("The path to a local copy of the RustSec advisory-db, either a directory or a .zip of it, skips downloading the database")
+        )
+        .arg(Arg::with_name("deny-informational")
+            .long("deny-informational")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(true)
+            .required(false)
+            .possible_values(&["unmaintained", "unsound", "notice"])
+            .help("Counts the listed kinds of informational advisories as vulnerabilities, separated by commas")
+        )
+        .arg(Arg::with_name("show-withdrawn")
+            .long("show-withdrawn")
+            .takes_value(false)
+            .required(false)
+            .help("Lists advisories which have since been withdrawn, these are never counted as vulnerabilities")
+        )
+        .arg(Arg::with_name("target")
+            .long("target")
+            .takes_value(true)
+            .required(false)
+            .help("The target triple to check platform specific advisories against, defaults to the current platform. Also leaves out target specific dependencies whose cfg doesn't match it")
+        )
+        .arg(Arg::with_name("min-severity")
+            .long("min-severity")
+            .takes_value(true)
+            .required(false)
+            .possible_values(&["low", "medium", "high", "critical"])
+            .help("Ignores advisories with a CVSS severity below this level when colouring rows and deciding the exit status")
+        )
+        .arg(Arg::with_name("config")
+            .short("c")
+            .long("config")
+            .takes_value(true)
+            .required(false)
+            .help("The path to a version-checker.toml file, defaults to the one beside the manifest if it exists")
+        )
+        .arg(Arg::with_name("osv")
+            .long("osv")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .required(false)
+            .help("The path to a directory of OSV JSON advisories to check alongside the RustSec database, can be given more than once")
+        )
+        .arg(Arg::with_name("db-url")
+            .long("db-url")
+            .takes_value(true)
+            .required(false)
+            .help("Downloads the advisory database archive from this URL instead of GitHub, such as a mirror")
+        )
+        .arg(Arg::with_name("db-sha256")
+            .long("db-sha256")
+            .takes_value(true)
+            .required(false)
+            .help("The expected SHA-256 of the downloaded advisory database archive")
+        )
+        .arg(Arg::with_name("db-checksum-url")
+            .long("db-checksum-url")
+            .takes_value(true)
+            .required(false)
+            .help("The URL of a sha256sum style checksum file to verify the downloaded advisory database archive against")
+        )
+        .arg(Arg::with_name("cache-dir")
+            .long("cache-dir")
+            .takes_value(true)
+            .required(false)
+            .help("The directory to store the advisory database in, defaults to the user's cache directory")
+        )
+        .arg(Arg::with_name("details")
+            .long("details")
+            .takes_value(false)
+            .required(false)
+            .help("Shows the title, date, severity, patched versions, URL and description of each advisory beneath the dependency it affects")
+        )
+        .arg(Arg::with_name("lockfile")
+            .long("lockfile")
+            .takes_value(true)
+            .required(false)
+            .help("The path to a Cargo.lock file to read the resolved versions of dependencies from, defaults to the one beside the manifest if it exists")
+        )
+        .arg(Arg::with_name("lockfile-only")
+            .long("lockfile-only")
+            .takes_value(false)
+            .required(false)
+            .help("Checks every package in the lockfile without reading a manifest, packages from path or git sources aren't looked up on crates.io")
+        )
+        .arg(Arg::with_name("include-kind")
+            .long("include-kind")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(true)
+            .required(false)
+            .possible_values(&["normal", "dev", "build"])
+            .help("Only checks the listed kinds of dependencies, separated by commas, defaults to all of them")
+        )
+        .arg(Arg::with_name("exclude-kind")
+            .long("exclude-kind")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(true)
+            .required(false)
+            .possible_values(&["normal", "dev", "build"])
+            .help("Leaves out the listed kinds of dependencies, separated by commas")
+        )
+        .subcommand(SubCommand::with_name("advisory")
+            .about("Looks up entries in the advisory database")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("show")
+                .about("Shows every detail of an advisory")
+                .arg(Arg::with_name("id")
+                    .required(true)
+                    .help("The id of the advisory, or one of its aliases such as a CVE")
+                )
+            )
+            .subcommand(SubCommand::with_name("search")
+                .about("Lists the advisories whose id, aliases, crate, keywords or categories contain the keyword")
+                .arg(Arg::with_name("keyword")
+                    .required(true)
+                    .help("The text to search for, ignoring case")
+                )
+            )
+            .subcommand(SubCommand::with_name("list")
+                .about("Lists the advisories for a crate")
+                .arg(Arg::with_name("crate")
+                    .long("crate")
+                    .takes_value(true)
+                    .required(true)
+                    .help("The name of the crate")
+                )
+            )
+            .subcommand(SubCommand::with_name("check")
+                .about("Checks whether the manifest's dependencies are affected by advisories given by their RUSTSEC, CVE or GHSA ids, exits with 1 if any are")
+                .arg(Arg::with_name("ids")
+                    .required(true)
+                    .multiple(true)
+                    .help("The ids of the advisories, or their aliases")
+                )
+            )
+        )
         .get_matches();
 
     let mut recursion = 0;
     let mut updates = true;
+    let mut offline = false;
+    let mut show_withdrawn = false;
+    let mut details = false;
+    let mut lockfile_only = false;
 
     if matches.is_present("deep") {
         recursion = 1;
     }
 
+    if let Some(depth) = matches.value_of("depth") {
+        recursion = depth.parse::<usize>().unwrap();
+    }
+
     if matches.is_present("no-update") {
         updates = false;
     }
 
+    if matches.is_present("offline") {
+        offline = true;
+    }
+
+    if matches.is_present("show-withdrawn") {
+        show_withdrawn = true;
+    }
+
+    if matches.is_present("details") {
+        details = true;
+    }
+
+    if matches.is_present("lockfile-only") {
+        lockfile_only = true;
+    }
+
     let manifest = matches.value_of("manifest");
+    let advisory_db = matches.value_of("advisory-db");
+    let target = matches.value_of("target");
+    let config = matches.value_of("config");
+    let db_url = matches.value_of("db-url");
+    let db_sha256 = matches.value_of("db-sha256");
+    let db_checksum_url = matches.value_of("db-checksum-url");
+    let cache_dir = matches.value_of("cache-dir");
+    let lockfile = matches.value_of("lockfile");
+    let osv: Vec<String> = matches.values_of("osv").map(|paths| paths.map(|path| path.to_string()).collect()).unwrap_or_default();
+    let min_severity = Severity::from_name(matches.value_of("min-severity").unwrap_or("none"));
+    let deny_informational: Vec<String> = matches.values_of("deny-informational").map(|kinds| kinds.map(|kind| kind.to_string()).collect()).unwrap_or_default();
+    let db_max_age = matches.value_of("db-max-age").unwrap().parse::<u64>().unwrap();
+    let included: Vec<DependencyKind> = matches.values_of("include-kind").map(|kinds| kinds.filter_map(DependencyKind::from_name).collect()).unwrap_or_default();
+    let excluded: Vec<DependencyKind> = matches.values_of("exclude-kind").map(|kinds| kinds.filter_map(DependencyKind::from_name).collect()).unwrap_or_default();
+    let kinds: Vec<DependencyKind> = vec![DependencyKind::Normal, DependencyKind::Dev, DependencyKind::Build].into_iter()
+        .filter(|kind| (included.is_empty() || included.contains(kind)) && !excluded.contains(kind))
+        .collect();
+
+    let args = utilities::terminal::input::Arguments {
+        manifest,
+        recursion,
+        updates,
+        db_max_age,
+        offline,
+        advisory_db,
+        deny_informational,
+        show_withdrawn,
+        target,
+        min_severity,
+        config,
+        osv,
+        db_url,
+        db_sha256,
+        db_checksum_url,
+        cache_dir,
+        details,
+        lockfile,
+        lockfile_only,
+        kinds,
+    };
 
-    utilities::terminal::input::parse_args(manifest, recursion, updates)
+    if let Some(advisory) = matches.subcommand_matches("advisory") {
+        let query = match advisory.subcommand() {
+            ("show", Some(show)) => AdvisoryQuery::Show(show.value_of("id").unwrap()),
+            ("search", Some(search)) => AdvisoryQuery::Search(search.value_of("keyword").unwrap()),
+            ("list", Some(list)) => AdvisoryQuery::List(list.value_of("crate").unwrap()),
+            ("check", Some(check)) => AdvisoryQuery::Check(check.values_of("ids").unwrap().collect()),
+            _ => return,
+        };
+
+        utilities::terminal::input::advisory_command(args, query)
+    } else {
+        utilities::terminal::input::parse_args(args)
+    }
 }
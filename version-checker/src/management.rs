@@ -1,2 +1,9 @@
 pub mod security;
-pub mod crates_io;
\ No newline at end of file
+pub mod crates_io;
+pub mod reachability;
+pub mod cvss;
+pub mod osv;
+pub mod toolchain;
+pub mod lockfile;
+pub mod workspace;
+pub mod platform;
@@ -1,15 +1,23 @@
 use crates_io_api::{SyncClient, CrateResponse, Error};
 use crate::utilities::errors::{VerificationError, Errors};
-use std::fs::OpenOptions;
-use std::io::Read;
 use std::path::Path;
-use cargo_toml::Manifest;
 use std::fmt;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use serde::__private::Formatter;
 use regex::Regex;
-use crate::utilities::terminal::output::{DisplayLine, OutputManager, OutputDisplayType};
+use crate::utilities::terminal::output::{DisplayLine, OutputManager, OutputDisplayType, advisory_title};
+use crate::utilities::terminal::markdown;
 use crate::management::security::SecurityDatabase;
-use std::process::Command;
+use crate::management::reachability::SourceIndex;
+use crate::management::cvss::Cvss;
+use crate::management::lockfile;
+use crate::management::workspace;
+use crate::management::platform::Target;
+use crate::utilities::serial::lockfile::{Lockfile, LockedPackage};
+use crate::utilities::serial::config::IgnoredAdvisory;
+use crate::utilities::serial::security::ParentalAdvisory;
+use semver::VersionReq;
 
 #[derive(Debug, Clone)]
 pub struct Dependency {
@@ -17,6 +25,27 @@ pub struct Dependency {
     pub name: String,
     pub version: Version,
     pub remote: Version,
+    pub requirement: Option<String>,
+    pub kind: DependencyKind,
+    pub target: Option<String>,
+    pub optional: bool,
+}
+
+/// Which section of the manifest a dependency is listed in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+}
+
+/// A dependency as it is listed in a manifest, or found in the dependency graph
+#[derive(Debug, Clone)]
+pub struct ManifestEntry {
+    pub name: String,
+    pub dependency: cargo_toml::Dependency,
+    pub kind: DependencyKind,
+    pub target: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -48,6 +77,47 @@ impl fmt::Display for Version {
     }
 }
 
+impl fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyKind::Normal => write!(f, "normal"),
+            DependencyKind::Dev => write!(f, "dev"),
+            DependencyKind::Build => write!(f, "build"),
+        }
+    }
+}
+
+impl DependencyKind {
+    pub fn from_name(name: &str) -> Option<DependencyKind> {
+        match name {
+            "normal" => Some(DependencyKind::Normal),
+            "dev" => Some(DependencyKind::Dev),
+            "build" => Some(DependencyKind::Build),
+            _ => None,
+        }
+    }
+
+    /// The key of the manifest table listing dependencies of this kind
+    pub fn section(&self) -> &str {
+        match self {
+            DependencyKind::Normal => "dependencies",
+            DependencyKind::Dev => "dev-dependencies",
+            DependencyKind::Build => "build-dependencies",
+        }
+    }
+}
+
+impl ManifestEntry {
+    pub fn new(name: String, dependency: cargo_toml::Dependency) -> ManifestEntry {
+        ManifestEntry {
+            name,
+            dependency,
+            kind: DependencyKind::Normal,
+            target: None,
+        }
+    }
+}
+
 impl Dependency {
     pub fn new(name: &str, version: &str, remote: Version) -> Dependency {
         let prefixes = Regex::new(r#"[><=^*~ ]"#).unwrap();
@@ -143,8 +213,33 @@ impl Dependency {
                 }
             },
             remote,
+            requirement: None,
+            kind: DependencyKind::Normal,
+            target: None,
+            optional: false,
         }
     }
+
+    /// A dependency at the version cargo locked it to, keeping the requirement from the manifest to show beside it
+    pub fn locked(name: &str, requirement: &str, locked: &semver::Version, remote: Version) -> Dependency {
+        let mut dependency = Dependency::new(name, locked.to_string().as_str(), remote);
+        dependency.requirement = Some(requirement.to_string());
+        dependency
+    }
+}
+
+/// The advisories which apply to a single dependency
+#[derive(Debug, Clone, Default)]
+pub struct AdvisoryCount {
+    pub vulnerabilities: u16,
+    pub informational: u16,
+    pub withdrawn: u16,
+    pub filtered: u16,
+    pub below: u16,
+    pub score: Option<f64>,
+    pub matched: Vec<ParentalAdvisory>,
+    pub ignored: Vec<(String, IgnoredAdvisory)>,
+    pub withdrawals: Vec<(String, String)>,
 }
 
 pub struct CratesIOManager {
@@ -153,6 +248,14 @@ pub struct CratesIOManager {
     pub utd: u16,
     pub ood: u16,
     pub sav: u16,
+    pub sources: SourceIndex,
+    pub details: bool,
+    pub lockfile: Option<Lockfile>,
+    pub checked: RefCell<HashMap<String, (Dependency, AdvisoryCount, usize)>>,
+    pub root: RefCell<Option<String>>,
+    pub ancestors: RefCell<Vec<String>>,
+    pub kinds: Vec<DependencyKind>,
+    pub target: Option<Target>,
 }
 
 impl CratesIOManager {
@@ -166,6 +269,14 @@ impl CratesIOManager {
             utd: 0,
             ood: 0,
             sav: 0,
+            sources: SourceIndex::default(),
+            details: false,
+            lockfile: None,
+            checked: RefCell::new(HashMap::new()),
+            root: RefCell::new(None),
+            ancestors: RefCell::new(vec![]),
+            kinds: vec![DependencyKind::Normal, DependencyKind::Dev, DependencyKind::Build],
+            target: None,
         }
     }
 
@@ -228,43 +339,213 @@ impl CratesIOManager {
         }
     }
 
-    pub fn fetch_dependencies<P: AsRef<Path>>(&self, path_to_manifest: P, output: &OutputManager, db: &SecurityDatabase, recursion: usize) -> Result<(u16, u16, u16, u16), VerificationError> {
-        let (mut good, mut bad, mut insecure, mut warn) = (0, 0, 0, 0);
-        let handle = OpenOptions::new().write(true).read(true).create(false).open(path_to_manifest.as_ref());
-        return if let Ok(mut file) = handle {
-            let mut content_string = String::new();
+    /// Checks the dependencies of a manifest, or of every member when it is the root of a workspace, giving each member
+    /// its own section followed by a summary of the whole workspace
+    pub fn fetch_dependencies<P: AsRef<Path>>(&self, path_to_manifest: P, output: &OutputManager, db: &SecurityDatabase, recursion: usize) -> Result<(u16, u16, u16, u16, u16), VerificationError> {
+        let raw = workspace::read_manifest(path_to_manifest.as_ref())?;
 
-            let read_result = file.read_to_string(&mut content_string);
+        if let Some(members) = workspace::members(path_to_manifest.as_ref(), &raw) {
+            let (mut good, mut bad, mut insecure, mut warn, mut info) = (0, 0, 0, 0, 0);
+            let shared = workspace::shared_dependencies(&raw);
+            let mut summary: Vec<DisplayLine> = vec![];
+
+            for member in members.iter() {
+                let member_raw = workspace::read_manifest(member.as_path())?;
+                let (g, b, i, w, n) = self.check_manifest(&member_raw, &shared, output, db, recursion)?;
+
+                // Each member's section lists all of its own dependencies, even those already shown for another member
+                self.checked.borrow_mut().clear();
 
-            if read_result.is_ok() {
-                let manifest: Manifest = toml::from_str(content_string.as_str()).unwrap();
-                if let Some(package) = manifest.package {
-                    output::render(DisplayLine::new_title(format!("Version Report: {}", package.name).as_str()));
-                } else {
-                    output::render(DisplayLine::new_title("Version Report: Unknown Package"));
-                }
-                output::render(DisplayLine::new_header());
                 output::render(DisplayLine::new_guide());
+                output::render(DisplayLine::new_footer());
+                output::render(DisplayLine::new_guide());
+                output::render(DisplayLine::new_footer_content(g, b, i, w, n));
+                output::render(DisplayLine::new_table_end());
 
-                let tree_result = Command::new("cargo").args(&["tree", "--no-dedupe", "--edges", "all"]).output();
+                let name = member_raw.get("package").and_then(|package| package.get("name")).and_then(|name| name.as_str())
+                    .map(|name| name.to_string())
+                    .unwrap_or(member.to_string_lossy().to_string());
+                summary.push(DisplayLine::new_summary_content(name.as_str(), g, b, i, w, n));
 
-                if let Ok(tree) = tree_result {
+                good += g;
+                bad += b;
+                insecure += i;
+                warn += w;
+                info += n;
+            }
 
-                    Ok((good, bad, insecure, warn))
-                } else {
-                    Err(VerificationError::new(Errors::CrateFileNotFound))
-                }
-            } else {
-                Err(VerificationError::new(Errors::CrateFileNotFound))
+            // The combined totals follow as the footer of this table
+            output::render(DisplayLine::new_title(format!("Workspace Summary: {} members", members.len()).as_str()));
+            output::render(DisplayLine::new_summary_header());
+            output::render(DisplayLine::new_guide());
+            for row in summary {
+                output::render(row);
             }
+            return Ok((good, bad, insecure, warn, info));
+        }
+
+        let shared = workspace::find_root(path_to_manifest.as_ref()).map(|root| workspace::shared_dependencies(&root)).unwrap_or_default();
+        self.check_manifest(&raw, &shared, output, db, recursion)
+    }
+
+    fn check_manifest(&self, raw: &toml::Value, shared: &HashMap<String, toml::Value>, output: &OutputManager, db: &SecurityDatabase, recursion: usize) -> Result<(u16, u16, u16, u16, u16), VerificationError> {
+        let (mut good, mut bad, mut insecure, mut warn, mut info) = (0, 0, 0, 0, 0);
+
+        // Direct dependencies are resolved through the edges of this package in Cargo.lock
+        let package_name = raw.get("package").and_then(|package| package.get("name")).and_then(|name| name.as_str());
+        *self.root.borrow_mut() = package_name.map(|name| name.to_string());
+
+        if let Some(name) = package_name {
+            output::render(DisplayLine::new_title(format!("Version Report: {}", name).as_str()));
         } else {
-            Err(VerificationError::new(Errors::CrateFileNotFound))
+            output::render(DisplayLine::new_title("Version Report: Unknown Package"));
+        }
+        output::render(DisplayLine::new_header());
+        output::render(DisplayLine::new_guide());
+
+        let entries = manifest_entries(raw, shared)?;
+
+        for kind in self.kinds.iter() {
+            let included: Vec<&ManifestEntry> = entries.iter().filter(|entry| {
+                entry.kind == *kind && entry.target.as_ref().map_or(true, |spec| self.target.as_ref().map_or(true, |target| target.matches(spec)))
+            }).collect();
+
+            if included.is_empty() {
+                continue;
+            }
+            if *kind != DependencyKind::Normal {
+                output::render(DisplayLine::new_group(kind.section()));
+            }
+
+            for entry in included {
+                let (g, b, i, w, n) = manage_deps(self, entry.clone(), db, output, recursion, false, "");
+                good += g;
+                bad += b;
+                insecure += i;
+                warn += w;
+                info += n;
+            }
+        }
+
+        Ok((good, bad, insecure, warn, info))
+    }
+
+    /// Checks every package listed in the lockfile, without reading a manifest
+    pub fn check_lockfile(&self, output: &OutputManager, db: &SecurityDatabase) -> Result<(u16, u16, u16, u16, u16), VerificationError> {
+        let (mut good, mut bad, mut insecure, mut warn, mut info) = (0, 0, 0, 0, 0);
+        let lockfile = self.lockfile.as_ref().ok_or(VerificationError::new(Errors::LockfileInvalid))?;
+
+        output::render(DisplayLine::new_title("Version Report: Cargo.lock"));
+        output::render(DisplayLine::new_header());
+        output::render(DisplayLine::new_guide());
+
+        for package in lockfile.package.clone().unwrap_or_default().iter() {
+            let (g, b, i, w, n) = manage_locked_package(self, package, db, output);
+            good += g;
+            bad += b;
+            insecure += i;
+            warn += w;
+            info += n;
+        }
+
+        Ok((good, bad, insecure, warn, info))
+    }
+}
+
+/// Reads the dependencies listed in a manifest, or in every member of a workspace, without looking them up on crates.io.
+/// Renamed dependencies are keyed by their crate name
+pub fn manifest_dependencies<P: AsRef<Path>>(path_to_manifest: P, lockfile: Option<&Lockfile>) -> Result<Vec<Dependency>, VerificationError> {
+    let raw = workspace::read_manifest(path_to_manifest.as_ref())?;
+    let (manifests, shared) = match workspace::members(path_to_manifest.as_ref(), &raw) {
+        Some(members) => (
+            members.iter().map(|member| workspace::read_manifest(member.as_path())).collect::<Result<Vec<toml::Value>, VerificationError>>()?,
+            workspace::shared_dependencies(&raw),
+        ),
+        None => (
+            vec![raw],
+            workspace::find_root(path_to_manifest.as_ref()).map(|root| workspace::shared_dependencies(&root)).unwrap_or_default(),
+        ),
+    };
+    let mut dependencies: Vec<Dependency> = vec![];
+
+    for manifest in manifests.iter() {
+        let root = manifest.get("package").and_then(|package| package.get("name")).and_then(|name| name.as_str());
+
+        for entry in manifest_entries(manifest, &shared)? {
+            let (crate_name, requirement) = dependency_requirement(entry.name, entry.dependency);
+            let unknown = Version {
+                is_semver: false,
+                is_provided: false,
+                prefixes: None,
+                semver: None,
+                normal: None,
+            };
+
+            dependencies.push(match lockfile.and_then(|lockfile| lockfile::resolve_direct(lockfile, root, crate_name.as_str(), requirement.as_str())) {
+                Some(locked) => Dependency::locked(crate_name.as_str(), requirement.as_str(), &locked, unknown),
+                None => Dependency::new(crate_name.as_str(), requirement.as_str(), unknown),
+            });
+        }
+    }
+
+    Ok(dependencies)
+}
+
+/// Every package locked in a lockfile, including those only depended on indirectly
+pub fn locked_dependencies(lockfile: &Lockfile) -> Vec<Dependency> {
+    lockfile.package.clone().unwrap_or_default().iter().map(|package| {
+        let unknown = Version {
+            is_semver: false,
+            is_provided: false,
+            prefixes: None,
+            semver: None,
+            normal: None,
         };
+
+        Dependency::new(package.name.as_str(), package.version.as_str(), unknown)
+    }).collect()
+}
+
+/// Every dependency a manifest lists, in its `[dependencies]`, `[dev-dependencies]` and `[build-dependencies]` tables
+/// and in those under `[target.'...']`, with dependencies declared with `workspace = true` resolved
+pub fn manifest_entries(raw: &toml::Value, shared: &HashMap<String, toml::Value>) -> Result<Vec<ManifestEntry>, VerificationError> {
+    let mut tables: Vec<(Option<String>, &toml::Value)> = vec![(None, raw)];
+    let mut entries: Vec<ManifestEntry> = vec![];
+
+    if let Some(targets) = raw.get("target").and_then(|targets| targets.as_table()) {
+        for (spec, table) in targets {
+            tables.push((Some(spec.clone()), table));
+        }
+    }
+
+    for (target, table) in tables {
+        for kind in &[DependencyKind::Normal, DependencyKind::Dev, DependencyKind::Build] {
+            let listed = table.get(kind.section()).and_then(|listed| listed.as_table()).cloned().unwrap_or_default();
+
+            for (name, value) in listed {
+                let dependency: cargo_toml::Dependency = workspace::inherit(name.as_str(), &value, shared).try_into()
+                    .map_err(|_| VerificationError::new(Errors::CrateFileNotFound))?;
+
+                entries.push(ManifestEntry {
+                    name,
+                    dependency,
+                    kind: *kind,
+                    target: target.clone(),
+                });
+            }
+        }
     }
+
+    Ok(entries)
 }
 
 pub fn process_dependency(client: &CratesIOManager, name: String, dependency: cargo_toml::Dependency) -> Dependency {
-    let remote_result: Result<CrateResponse, Error> = client.client.get_crate(name.as_str());
+    let optional = match &dependency {
+        cargo_toml::Dependency::Detailed(detail) => detail.optional,
+        _ => false,
+    };
+    let (crate_name, requirement) = dependency_requirement(name, dependency);
+    let remote_result: Result<CrateResponse, Error> = client.client.get_crate(crate_name.as_str());
     let mut remote_version: Version = Version {
         is_semver: false,
         is_provided: false,
@@ -312,17 +593,93 @@ pub fn process_dependency(client: &CratesIOManager, name: String, dependency: ca
         }
     }
 
+    let root = client.root.borrow().clone();
+    let locked = client.lockfile.as_ref().and_then(|lockfile| lockfile::resolve_direct(lockfile, root.as_deref(), crate_name.as_str(), requirement.as_str()));
+    let mut dep = match locked {
+        Some(locked) => Dependency::locked(crate_name.as_str(), requirement.as_str(), &locked, remote_version),
+        None => Dependency::new(crate_name.as_str(), requirement.as_str(), remote_version),
+    };
+
+    dep.optional = optional;
+    dep
+}
+
+/// The crate a manifest entry refers to, which differs from the entry's name when it is renamed, and its version requirement
+fn dependency_requirement(name: String, dependency: cargo_toml::Dependency) -> (String, String) {
     match dependency {
-        cargo_toml::Dependency::Simple(version) => {
-            Dependency::new(name.as_str(), version.as_str(), remote_version)
-        }
-        cargo_toml::Dependency::Detailed(manifest) => {
-            if let Some(version) = manifest.version {
-                Dependency::new(name.as_str(), version.as_str(), remote_version)
-            } else {
-                Dependency::new(name.as_str(), "", remote_version)
-            }
-        }
+        cargo_toml::Dependency::Simple(version) => (name, version),
+        cargo_toml::Dependency::Detailed(manifest) => (manifest.package.unwrap_or(name), manifest.version.unwrap_or_default()),
+    }
+}
+
+/// Identifies a package in the dependency graph by its name and locked version, or its requirement when it isn't locked
+fn dependency_key(client: &CratesIOManager, entry: &ManifestEntry) -> String {
+    let (crate_name, requirement) = dependency_requirement(entry.name.clone(), entry.dependency.clone());
+    let root = client.root.borrow().clone();
+    let locked = client.lockfile.as_ref().and_then(|lockfile| lockfile::resolve_direct(lockfile, root.as_deref(), crate_name.as_str(), requirement.as_str()));
+
+    match locked {
+        Some(version) => format!("{} {}", crate_name, version),
+        None => format!("{} {}", crate_name, requirement),
+    }
+}
+
+/// The dependencies of a package, from the resolved graph in Cargo.lock when there is one, otherwise as published on crates.io
+fn dependency_children(client: &CratesIOManager, dep: &Dependency) -> Vec<ManifestEntry> {
+    if let Some(lockfile) = client.lockfile.as_ref() {
+        let version = dep.version.to_string();
+        let package = lockfile.package.as_ref().and_then(|packages| {
+            packages.iter().find(|package| package.name == dep.name && package.version == version)
+        });
+
+        return package.map(|package| {
+            package.dependencies.clone().unwrap_or_default().iter()
+                .filter_map(|entry| lockfile::find_package(lockfile, entry))
+                .map(|child| child_entry(dep, child.name.clone(), format!("={}", child.version), false))
+                .collect()
+        }).unwrap_or_default();
+    }
+
+    let crate_deps: Result<Vec<crates_io_api::Dependency>, Error> = client.client.crate_dependencies(dep.name.as_str(), dep.version.to_string().as_str());
+
+    // A published crate's own dev-dependencies are only used to test it, so they aren't part of the graph
+    crate_deps.map(|dependencies| {
+        dependencies.into_iter()
+            .filter(|dependency| dependency.kind != "dev")
+            .map(|dependency| child_entry(dep, dependency.crate_id, dependency.req, dependency.optional))
+            .collect()
+    }).unwrap_or_default()
+}
+
+/// A dependency of a package, which is only needed for the same kind of build as the package itself,
+/// so the dependencies of a dev-dependency are listed as dev-dependencies too
+fn child_entry(parent: &Dependency, name: String, requirement: String, optional: bool) -> ManifestEntry {
+    let dependency = if optional {
+        cargo_toml::Dependency::Detailed(cargo_toml::DependencyDetail {
+            version: Some(requirement),
+            optional,
+            ..Default::default()
+        })
+    } else {
+        cargo_toml::Dependency::Simple(requirement)
+    };
+    let mut entry = ManifestEntry::new(name, dependency);
+    entry.kind = parent.kind;
+    entry
+}
+
+/// The indentation shown before the branches of a package's dependencies, continuing the lines of the branches above it
+fn child_prefix(did_recurse: bool, indenter: &str) -> String {
+    if !did_recurse {
+        return "".to_string();
+    }
+
+    if let Some(prefix) = indenter.strip_suffix("┗━") {
+        format!("{}  ", prefix)
+    } else if let Some(prefix) = indenter.strip_suffix("┣━") {
+        format!("{}┃ ", prefix)
+    } else {
+        indenter.to_string()
     }
 }
 
@@ -334,64 +691,273 @@ fn check_diff(local: Version, remote: Version) -> bool {
     };
 }
 
-fn count_advisories(db: &SecurityDatabase, name: &str, local: &Version) -> u16 {
-    return if db.advisories.contains_key(name) {
-        let advisories = db.advisories.get(name).unwrap();
-        let mut applicable = 0;
+fn count_advisories(db: &SecurityDatabase, name: &str, local: &Version) -> AdvisoryCount {
+    let mut count = AdvisoryCount::default();
+
+    if let Some(advisories) = db.advisories.get(name) {
         for case in advisories {
-            if let Some(patch_info) = case.clone().versions {
-                if let Some(patched_in) = patch_info.patched {
-                    for version in patched_in {
-                        let prefixes = Regex::new(r#"[><=^*~ ]"#).unwrap();
-                        let mut ver = "".to_string();
-
-                        if prefixes.is_match(version.as_str()) {
-                            let chars: Vec<&str> = version.split("").collect();
-
-                            for character in chars {
-                                if !prefixes.is_match(character) && character != " " {
-                                    ver = format!("{}{}", ver, character);
-                                }
-                            }
-                        } else {
-                            let pieces: Vec<&str> = version.split(" ").collect();
-                            ver = pieces.join("");
-                        }
-                        let parse_attempt = semver::Version::parse(ver.as_str());
-
-                        if let Ok(parsed) = parse_attempt {
-                            if local.is_semver {
-                                if parsed > local.semver.clone().unwrap() {
-                                    applicable += 1;
-                                }
-                            } else {
-                                applicable += 1;
+            if is_withdrawn(case) {
+                if db.show_withdrawn && is_affected(case, local) {
+                    count.withdrawn += 1;
+                    count.withdrawals.push((advisory_id(case), case.advisory.clone().and_then(|details| details.withdrawn).unwrap_or_default()));
+                }
+            } else if is_affected(case, local) {
+                if let Some(entry) = db.ignore_entry(case) {
+                    count.ignored.push((advisory_id(case), entry));
+                } else if !db.is_platform_affected(case) {
+                    count.filtered += 1;
+                } else if db.is_vulnerability(case) {
+                    if db.meets_severity(case) {
+                        count.vulnerabilities += 1;
+                        count.matched.push(case.clone());
+
+                        if let Some(cvss) = Cvss::of(case) {
+                            if count.score.map_or(true, |score| cvss.score > score) {
+                                count.score = Some(cvss.score);
                             }
-                        } else {
-                            applicable += 1;
                         }
+                    } else {
+                        count.below += 1;
                     }
                 } else {
-                    applicable += 1;
+                    count.informational += 1;
+                    count.matched.push(case.clone());
                 }
+            }
+        }
+    }
+
+    count
+}
+
+fn advisory_id(advisory: &ParentalAdvisory) -> String {
+    advisory.advisory.clone().and_then(|details| details.id).unwrap_or("Advisory".to_string())
+}
+
+fn is_withdrawn(advisory: &ParentalAdvisory) -> bool {
+    advisory.advisory.clone().and_then(|details| details.withdrawn).is_some()
+}
+
+/// Checks a local version against the `patched` and `unaffected` ranges of an advisory,
+/// a version is only affected when it satisfies neither of them
+pub fn is_affected(advisory: &ParentalAdvisory, local: &Version) -> bool {
+    return if let Some(version) = local.semver.clone() {
+        if let Some(ranges) = advisory.versions.clone() {
+            let patched = parse_requirements(ranges.patched);
+            let unaffected = parse_requirements(ranges.unaffected);
+
+            !patched.iter().any(|req| req.matches(&version)) && !unaffected.iter().any(|req| req.matches(&version))
+        } else {
+            true
+        }
+    } else {
+        true
+    };
+}
+
+/// Turns the range strings of an advisory into requirements, dropping any which fail to parse
+pub fn parse_requirements(ranges: Option<Vec<String>>) -> Vec<VersionReq> {
+    let mut requirements: Vec<VersionReq> = vec![];
+
+    if let Some(ranges) = ranges {
+        for range in ranges {
+            if let Ok(req) = VersionReq::parse(range.trim()) {
+                requirements.push(req);
+            }
+        }
+    }
+
+    requirements
+}
+
+/// The functions named by an advisory which apply to the local version
+pub fn affected_functions(advisory: &ParentalAdvisory, local: &Version) -> Vec<String> {
+    let mut paths: Vec<String> = vec![];
+
+    if let Some(functions) = advisory.affected.clone().and_then(|affected| affected.functions) {
+        for (path, ranges) in functions {
+            let requirements = parse_requirements(Some(ranges));
+            let applies = if let Some(version) = local.semver.clone() {
+                requirements.is_empty() || requirements.iter().any(|req| req.matches(&version))
             } else {
-                applicable += 1;
+                true
+            };
+
+            if applies {
+                paths.push(path);
             }
         }
-        applicable
+    }
+
+    paths.sort();
+    paths
+}
+
+/// Builds a note naming an advisory and the source it came from, followed by where the functions
+/// it names are used in the project, if it names any
+fn advisory_note(client: &CratesIOManager, advisory: &ParentalAdvisory, local: &Version) -> DisplayLine {
+    let functions = affected_functions(advisory, local);
+    let source = advisory.source.clone().unwrap_or("unknown".to_string());
+    let text = format!("{} ({})", advisory_id(advisory), source);
+
+    if functions.is_empty() {
+        return DisplayLine::new_note(text.as_str(), "\x1b[90m");
+    }
+
+    let reference = functions.iter().find_map(|path| client.sources.find_reference(path));
+
+    if let Some((file, line)) = reference {
+        DisplayLine::new_note(format!("{}: referenced in {}:{}", text, file, line).as_str(), "\x1b[31m")
     } else {
-        0
+        DisplayLine::new_note(format!("{}: no direct reference found", text).as_str(), "\x1b[90m")
+    }
+}
+
+/// The indented block shown beneath an advisory's note in `--details` mode
+fn advisory_details(advisory: &ParentalAdvisory, width: usize) -> Vec<DisplayLine> {
+    let details = advisory.advisory.clone().unwrap_or_default();
+    let mut lines: Vec<String> = vec![];
+
+    if let Some(title) = advisory_title(advisory) {
+        lines.push(format!("\x1b[1m{}\x1b[0m", title));
+    }
+    if let Some(date) = details.date {
+        lines.push(format!("Date: {}", date));
+    }
+    if let Some(cvss) = Cvss::of(advisory) {
+        lines.push(format!("Severity: {} ({:.1})", cvss.severity, cvss.score));
+    }
+    let patched = advisory.versions.clone().and_then(|versions| versions.patched).unwrap_or_default();
+    if patched.is_empty() {
+        lines.push("Patched: no patched versions".to_string());
+    } else {
+        lines.push(format!("Patched: {}", patched.join(", ")));
+    }
+    if let Some(url) = details.url {
+        lines.push(format!("URL: {}", url));
+    }
+    if let Some(body) = advisory.body.clone() {
+        lines.extend(markdown::render(&markdown::first_paragraph(body.as_str()), width));
+    }
+
+    lines.iter().map(|line| DisplayLine::new_note(format!("    {}", line).as_str(), "\x1b[0m")).collect()
+}
+
+/// The notes beneath a dependency's row, naming each advisory which applies to it and each one which was ignored
+fn render_advisory_notes(client: &CratesIOManager, dep: &Dependency, advisories: &AdvisoryCount, output: &OutputManager) {
+    for advisory in advisories.matched.iter() {
+        output::render(advisory_note(client, advisory, &dep.version));
+
+        if client.details {
+            for line in advisory_details(advisory, output.display_width - 12) {
+                output::render(line);
+            }
+        }
+    }
+
+    for (id, entry) in advisories.ignored.iter() {
+        let text = if let Some(until) = entry.until.clone() {
+            format!("{}: ignored until {}, {}", id, until, entry.reason)
+        } else {
+            format!("{}: ignored, {}", id, entry.reason)
+        };
+        output::render(DisplayLine::new_note(text.as_str(), "\x1b[90m"));
+    }
+
+    for (id, date) in advisories.withdrawals.iter() {
+        output::render(DisplayLine::new_note(format!("{}: withdrawn {}", id, date).as_str(), "\x1b[90m"));
+    }
+}
+
+/// Notes the `[target.'...']` table a target specific dependency is listed under
+fn render_target_note(dep: &Dependency, output: &OutputManager) {
+    if let Some(target) = dep.target.as_ref() {
+        output::render(DisplayLine::new_note(format!("only for {}", target).as_str(), "\x1b[90m"));
+    }
+}
+
+/// Checks a package read from a lockfile on its own. Packages from crates.io are checked like any other dependency,
+/// those from path or git sources are checked against the advisory database but not looked up on crates.io
+fn manage_locked_package(client: &CratesIOManager, package: &LockedPackage, db: &SecurityDatabase, output: &OutputManager) -> (u16, u16, u16, u16, u16) {
+    if lockfile::is_crates_io(package) {
+        let entry = ManifestEntry::new(package.name.clone(), cargo_toml::Dependency::Simple(format!("={}", package.version)));
+        return manage_deps(client, entry, db, output, 0, false, "");
+    }
+
+    let (mut insecure, mut info) = (0, 0);
+    let non_registry = Version {
+        is_semver: false,
+        is_provided: true,
+        prefixes: None,
+        semver: None,
+        normal: Some("non-registry".to_string()),
     };
+    let dep = Dependency::new(package.name.as_str(), package.version.as_str(), non_registry);
+    let advisories = count_advisories(db, dep.name.as_str(), &dep.version);
+    let mut row = DisplayLine::new_crate(dep.clone(), &advisories);
+
+    row.cells[3].color = "\x1b[90m".to_string();
+
+    if advisories.informational > 0 {
+        info += advisories.informational;
+        row.cells[0].color = "\x1b[33m".to_string();
+    }
+
+    if advisories.vulnerabilities > 0 {
+        insecure += advisories.vulnerabilities;
+        row.cells[0].color = "\x1b[31m".to_string();
+        row.cells[1].color = "\x1b[31m".to_string();
+        row.cells[2].color = "\x1b[31m".to_string();
+    }
+
+    output::render(row);
+    render_advisory_notes(client, &dep, &advisories, output);
+
+    // Counted with the dependencies whose latest version is unknown, so they are part of the total
+    (0, 0, insecure, 1, info)
 }
 
-pub fn manage_deps(client: &CratesIOManager, entry: (String, cargo_toml::Dependency), db: &SecurityDatabase, output: &OutputManager, recursion: usize, did_recurse: bool, indenter: &str) -> (u16, u16, u16, u16) {
-    let (mut good, mut bad, mut insecure, mut warn) = (0, 0, 0, 0);
-    let dep: Dependency = process_dependency(&client, entry.0, entry.1);
-    let count = count_advisories(db, dep.name.as_str(), &dep.version);
+pub fn manage_deps(client: &CratesIOManager, entry: ManifestEntry, db: &SecurityDatabase, output: &OutputManager, recursion: usize, did_recurse: bool, indenter: &str) -> (u16, u16, u16, u16, u16) {
+    let (mut good, mut bad, mut insecure, mut warn, mut info) = (0, 0, 0, 0, 0);
+    let key = dependency_key(client, &entry);
+
+    // Packages reached again through another path are shown without being checked or counted a second time, their
+    // dependencies are only walked again when this path has more depth left than the one they were checked through
+    let checked = client.checked.borrow().get(&key).cloned();
+    if let Some((dep, advisories, depth)) = checked {
+        let cycle = client.ancestors.borrow().contains(&key);
+        let mut shown = dep.clone();
+        shown.name = format!("{} {}", dep.name, if cycle { "(cycle)" } else { "(*)" });
+
+        let mut row = if !did_recurse {
+            DisplayLine::new_crate(shown, &advisories)
+        } else {
+            DisplayLine::new_crate_dep(shown, &advisories, indenter)
+        };
+        for cell in row.cells.iter_mut() {
+            cell.color = "\x1b[90m".to_string();
+        }
+        output::render(row);
+        render_target_note(&dep, output);
+
+        if cycle || depth >= recursion {
+            return (good, bad, insecure, warn, info);
+        }
+
+        client.checked.borrow_mut().insert(key.clone(), (dep.clone(), advisories, recursion));
+        return manage_children(client, &dep, key, db, output, recursion, did_recurse, indenter);
+    }
+
+    let mut dep: Dependency = process_dependency(&client, entry.name, entry.dependency);
+    dep.kind = entry.kind;
+    dep.target = entry.target;
+    let advisories = count_advisories(db, dep.name.as_str(), &dep.version);
+    client.checked.borrow_mut().insert(key.clone(), (dep.clone(), advisories.clone(), recursion));
+    let (count, informational) = (advisories.vulnerabilities, advisories.informational);
     let mut row = if !did_recurse {
-        DisplayLine::new_crate(dep.clone(), &count)
+        DisplayLine::new_crate(dep.clone(), &advisories)
     } else {
-        DisplayLine::new_crate_dep(dep.clone(), &count, indenter)
+        DisplayLine::new_crate_dep(dep.clone(), &advisories, indenter)
     };
 
     if !dep.version.is_provided {
@@ -415,6 +981,11 @@ pub fn manage_deps(client: &CratesIOManager, entry: (String, cargo_toml::Depende
         row.cells[3].color = "\x1b[32m".to_string();
     }
 
+    if informational > 0 {
+        info += informational;
+        row.cells[0].color = "\x1b[33m".to_string();
+    }
+
     if count > 0 {
         insecure += count;
         row.cells[0].color = "\x1b[31m".to_string();
@@ -426,8 +997,18 @@ pub fn manage_deps(client: &CratesIOManager, entry: (String, cargo_toml::Depende
         }
     }
 
+    output::render(row.clone());
+    render_target_note(&dep, output);
+    render_advisory_notes(client, &dep, &advisories, output);
+
+    let (g, b, i, w, n) = manage_children(client, &dep, key, db, output, recursion, did_recurse, indenter);
+    good += g;
+    bad += b;
+    insecure += i;
+    warn += w;
+    info += n;
+
     if did_recurse && indenter == "┗━" {
-        output::render(row.clone());
         let text = " ".to_string();
         row.cells[0].text = text.clone();
         row.cells[0].color = "\x1b[36m".to_string();
@@ -435,28 +1016,34 @@ pub fn manage_deps(client: &CratesIOManager, entry: (String, cargo_toml::Depende
         row.cells[2].text = text.clone();
         row.cells[3].text = text;
         row.display_type = OutputDisplayType::Entry;
+        output::render(row);
     }
 
-    output::render(row);
+    (good, bad, insecure, warn, info)
+}
 
-    if recursion > 0 {
-        let crate_deps: Result<Vec<crates_io_api::Dependency>, Error> = client.client.crate_dependencies(dep.name.as_str(), dep.version.to_string().as_str());
+/// Checks the dependencies of a package while there is depth left, drawing them as branches below it
+fn manage_children(client: &CratesIOManager, dep: &Dependency, key: String, db: &SecurityDatabase, output: &OutputManager, recursion: usize, did_recurse: bool, indenter: &str) -> (u16, u16, u16, u16, u16) {
+    let (mut good, mut bad, mut insecure, mut warn, mut info) = (0, 0, 0, 0, 0);
 
-        if let Ok(dependencies) = crate_deps {
-            for index in 0..dependencies.len() {
-                let dependency = dependencies[index].clone();
-                let (g, b, i, w) = if index == dependencies.len() - 1 {
-                    manage_deps(client, (dependency.crate_id.clone(), cargo_toml::Dependency::Simple(dependency.req)), db, output, 0, true, "┗━")
-                } else {
-                    manage_deps(client, (dependency.crate_id.clone(), cargo_toml::Dependency::Simple(dependency.req)), db, output, 0, true, "┣━")
-                };
-                good += g;
-                bad += b;
-                insecure += i;
-                warn += w;
-            }
-        }
+    if recursion == 0 {
+        return (good, bad, insecure, warn, info);
+    }
+
+    let children = dependency_children(client, dep);
+    let prefix = child_prefix(did_recurse, indenter);
+
+    client.ancestors.borrow_mut().push(key);
+    for index in 0..children.len() {
+        let branch = if index == children.len() - 1 { "┗━" } else { "┣━" };
+        let (g, b, i, w, n) = manage_deps(client, children[index].clone(), db, output, recursion - 1, true, format!("{}{}", prefix, branch).as_str());
+        good += g;
+        bad += b;
+        insecure += i;
+        warn += w;
+        info += n;
     }
+    client.ancestors.borrow_mut().pop();
 
-    (good, bad, insecure, warn)
+    (good, bad, insecure, warn, info)
 }
\ No newline at end of file
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::utilities::serial::security::ParentalAdvisory;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Severity {
+    None,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+/// The base score of a CVSS v3.x vector such as `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`
+#[derive(Debug, Clone, Copy)]
+pub struct Cvss {
+    pub score: f64,
+    pub severity: Severity,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::None => write!(f, "none"),
+            Severity::Low => write!(f, "low"),
+            Severity::Medium => write!(f, "medium"),
+            Severity::High => write!(f, "high"),
+            Severity::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+impl Severity {
+    pub fn from_name(name: &str) -> Severity {
+        match name.to_lowercase().as_str() {
+            "low" => Severity::Low,
+            "medium" => Severity::Medium,
+            "high" => Severity::High,
+            "critical" => Severity::Critical,
+            _ => Severity::None,
+        }
+    }
+
+    pub fn from_score(score: f64) -> Severity {
+        if score >= 9.0 {
+            Severity::Critical
+        } else if score >= 7.0 {
+            Severity::High
+        } else if score >= 4.0 {
+            Severity::Medium
+        } else if score > 0.0 {
+            Severity::Low
+        } else {
+            Severity::None
+        }
+    }
+}
+
+impl Cvss {
+    /// Parses the `cvss` field of an advisory, if it has one
+    pub fn of(advisory: &ParentalAdvisory) -> Option<Cvss> {
+        advisory.advisory.clone().and_then(|details| details.cvss).and_then(|vector| Cvss::parse(vector.as_str()))
+    }
+
+    pub fn parse(vector: &str) -> Option<Cvss> {
+        let mut pieces = vector.trim().split('/');
+
+        if !pieces.next()?.starts_with("CVSS:3") {
+            return None;
+        }
+
+        let mut metrics: HashMap<&str, &str> = HashMap::new();
+        for piece in pieces {
+            let mut pair = piece.splitn(2, ':');
+            metrics.insert(pair.next()?, pair.next()?);
+        }
+
+        let changed = match *metrics.get("S")? {
+            "U" => false,
+            "C" => true,
+            _ => return None,
+        };
+
+        let attack_vector = match *metrics.get("AV")? {
+            "N" => 0.85,
+            "A" => 0.62,
+            "L" => 0.55,
+            "P" => 0.2,
+            _ => return None,
+        };
+        let attack_complexity = match *metrics.get("AC")? {
+            "L" => 0.77,
+            "H" => 0.44,
+            _ => return None,
+        };
+        let privileges = match (*metrics.get("PR")?, changed) {
+            ("N", _) => 0.85,
+            ("L", false) => 0.62,
+            ("L", true) => 0.68,
+            ("H", false) => 0.27,
+            ("H", true) => 0.5,
+            _ => return None,
+        };
+        let interaction = match *metrics.get("UI")? {
+            "N" => 0.85,
+            "R" => 0.62,
+            _ => return None,
+        };
+
+        let mut impacts: Vec<f64> = vec![];
+        for metric in &["C", "I", "A"] {
+            impacts.push(match *metrics.get(metric)? {
+                "H" => 0.56,
+                "L" => 0.22,
+                "N" => 0.0,
+                _ => return None,
+            });
+        }
+
+        let base = 1.0 - (1.0 - impacts[0]) * (1.0 - impacts[1]) * (1.0 - impacts[2]);
+        let impact = if changed {
+            7.52 * (base - 0.029) - 3.25 * (base - 0.02).powi(15)
+        } else {
+            6.42 * base
+        };
+        let exploitability = 8.22 * attack_vector * attack_complexity * privileges * interaction;
+
+        let score = if impact <= 0.0 {
+            0.0
+        } else if changed {
+            round_up((1.08 * (impact + exploitability)).min(10.0))
+        } else {
+            round_up((impact + exploitability).min(10.0))
+        };
+
+        Some(Cvss {
+            score,
+            severity: Severity::from_score(score),
+        })
+    }
+}
+
+/// Rounds up to one decimal place, as defined in appendix A of the CVSS v3.1 specification
+fn round_up(value: f64) -> f64 {
+    let whole = (value * 100000.0).round() as i64;
+
+    if whole % 10000 == 0 {
+        whole as f64 / 100000.0
+    } else {
+        ((whole / 10000) + 1) as f64 / 10.0
+    }
+}
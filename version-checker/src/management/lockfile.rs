@@ -0,0 +1,68 @@
+use std::fs::read_to_string;
+use std::path::Path;
+
+use semver::VersionReq;
+
+use crate::utilities::errors::{Errors, VerificationError};
+use crate::utilities::serial::lockfile::{Lockfile, LockedPackage};
+
+pub fn read_lockfile(path: &Path) -> Result<Lockfile, VerificationError> {
+    let content = read_to_string(path).map_err(|_| VerificationError::new(Errors::LockfileInvalid))?;
+    toml::from_str(content.as_str()).map_err(|_| VerificationError::new(Errors::LockfileInvalid))
+}
+
+/// The version cargo locked for a dependency, the highest locked version of the package matching the requirement.
+/// When nothing matches, such as for path dependencies without a version, a package locked only once is used
+pub fn resolve(lockfile: &Lockfile, name: &str, requirement: &str) -> Option<semver::Version> {
+    let locked: Vec<semver::Version> = lockfile.package.clone().unwrap_or_default().iter()
+        .filter(|package| package.name == name)
+        .filter_map(|package| semver::Version::parse(package.version.as_str()).ok())
+        .collect();
+
+    let requirement = Some(requirement.trim()).filter(|requirement| !requirement.is_empty());
+    let matching = requirement.and_then(|requirement| VersionReq::parse(requirement).ok()).and_then(|req| {
+        locked.iter().filter(|version| req.matches(version)).max().cloned()
+    });
+
+    match matching {
+        Some(version) => Some(version),
+        None if locked.len() == 1 => locked.first().cloned(),
+        None => None,
+    }
+}
+
+/// The version cargo locked for a direct dependency of a package, read from the edges the package lists in Cargo.lock.
+/// Falls back to `resolve` when the package isn't locked or none of its edges to the crate satisfy the requirement
+pub fn resolve_direct(lockfile: &Lockfile, root: Option<&str>, name: &str, requirement: &str) -> Option<semver::Version> {
+    let edges = root.and_then(|root| {
+        lockfile.package.as_ref()?.iter().find(|package| package.name == root && package.source.is_none())
+    }).and_then(|package| package.dependencies.clone()).unwrap_or_default();
+
+    let requirement_parsed = Some(requirement.trim()).filter(|requirement| !requirement.is_empty()).and_then(|requirement| VersionReq::parse(requirement).ok());
+    let direct = edges.iter()
+        .filter_map(|entry| find_package(lockfile, entry))
+        .filter(|package| package.name == name)
+        .filter_map(|package| semver::Version::parse(package.version.as_str()).ok())
+        .filter(|version| requirement_parsed.as_ref().map_or(true, |req| req.matches(version)))
+        .max();
+
+    direct.or_else(|| resolve(lockfile, name, requirement))
+}
+
+/// Finds the locked package a dependency entry of the lockfile points at, entries are `name`, `name version` or `name version (source)`
+pub fn find_package<'a>(lockfile: &'a Lockfile, entry: &str) -> Option<&'a LockedPackage> {
+    let mut pieces = entry.split_whitespace();
+    let name = pieces.next()?;
+    let version = pieces.next();
+
+    lockfile.package.as_ref()?.iter().find(|package| {
+        package.name == name && version.map_or(true, |version| package.version == version)
+    })
+}
+
+/// Whether a locked package was downloaded from crates.io, rather than being a path or git dependency or from another registry
+pub fn is_crates_io(package: &LockedPackage) -> bool {
+    package.source.as_ref().map_or(false, |source| {
+        source == "registry+https://github.com/rust-lang/crates.io-index" || source == "sparse+https://index.crates.io/"
+    })
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use crate::utilities::serial::osv::{OsvAdvisory, OsvAffected, OsvRange};
+use crate::utilities::serial::security::{ParentalAdvisory, Advisory, Version, Affected};
+
+/// Maps an OSV document onto one advisory for each crates.io package it affects
+pub fn convert(document: OsvAdvisory) -> Vec<ParentalAdvisory> {
+    let mut packages: Vec<(String, Vec<OsvAffected>)> = vec![];
+
+    for affected in document.affected.clone().unwrap_or_default() {
+        if let Some(package) = affected.package.clone() {
+            if package.ecosystem != "crates.io" {
+                continue;
+            }
+
+            if let Some(entry) = packages.iter_mut().find(|(name, _)| *name == package.name) {
+                entry.1.push(affected);
+            } else {
+                packages.push((package.name, vec![affected]));
+            }
+        }
+    }
+
+    let cvss = document.severity.clone().unwrap_or_default().into_iter()
+        .find(|severity| severity.kind == "CVSS_V3")
+        .map(|severity| severity.score);
+    let url = document.references.clone().unwrap_or_default().into_iter()
+        .find(|reference| reference.kind == "ADVISORY" || reference.kind == "WEB")
+        .map(|reference| reference.url);
+    let body = match (document.summary.clone(), document.details.clone()) {
+        (Some(summary), Some(details)) => Some(format!("# {}\n\n{}", summary, details)),
+        (Some(summary), None) => Some(format!("# {}", summary)),
+        (None, details) => details,
+    };
+
+    packages.into_iter().map(|(name, affected)| {
+        let ranges: Vec<OsvRange> = affected.iter().flat_map(|entry| entry.ranges.clone().unwrap_or_default()).collect();
+        let affects = affected.iter().find_map(|entry| entry.ecosystem_specific.clone().and_then(|specific| specific.affects));
+        let specific = affected.iter().find_map(|entry| entry.database_specific.clone());
+
+        ParentalAdvisory {
+            body: body.clone(),
+            source: None,
+            advisory: Some(Advisory {
+                id: Some(document.id.clone()),
+                package: Some(name),
+                date: document.published.clone().map(|published| published.chars().take(10).collect()),
+                url: url.clone(),
+                categories: specific.clone().and_then(|specific| specific.categories),
+                keywords: None,
+                aliases: document.aliases.clone(),
+                cvss: cvss.clone(),
+                informational: specific.and_then(|specific| specific.informational),
+                withdrawn: document.withdrawn.clone(),
+            }),
+            versions: if ranges.is_empty() { None } else { Some(convert_ranges(&ranges)) },
+            affected: affects.map(|affects| Affected {
+                arch: affects.arch,
+                os: affects.os,
+                functions: affects.functions.map(|functions| {
+                    functions.into_iter().map(|function| (function, vec![])).collect::<HashMap<String, Vec<String>>>()
+                }),
+            }),
+        }
+    }).collect()
+}
+
+/// Collects the versions affected by every range, from each introduced event up to its fix or last affected version,
+/// and turns the gaps left between them into requirements. Gaps before anything is affected are unaffected, the rest are patched
+pub fn convert_ranges(ranges: &[OsvRange]) -> Version {
+    let mut affected: Vec<(Option<semver::Version>, Option<(semver::Version, bool)>)> = vec![];
+
+    for range in ranges {
+        if range.kind != "SEMVER" && range.kind != "ECOSYSTEM" {
+            continue;
+        }
+
+        let mut start: Option<Option<semver::Version>> = None;
+
+        for event in range.events.iter() {
+            if let Some(version) = event.get("introduced") {
+                start = match version.as_str() {
+                    "0" => Some(None),
+                    version => semver::Version::parse(version).ok().map(Some),
+                };
+            } else if let Some((version, inclusive)) = event.get("fixed").map(|version| (version, false))
+                .or_else(|| event.get("last_affected").map(|version| (version, true))) {
+                if let (Some(lower), Ok(upper)) = (start.take(), semver::Version::parse(version)) {
+                    affected.push((lower, Some((upper, inclusive))));
+                }
+            }
+        }
+
+        if let Some(lower) = start {
+            affected.push((lower, None));
+        }
+    }
+
+    affected.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut merged: Vec<(Option<semver::Version>, Option<(semver::Version, bool)>)> = vec![];
+
+    for (lower, upper) in affected {
+        if let Some(last) = merged.last_mut() {
+            let overlaps = match (&last.1, &lower) {
+                (None, _) | (_, None) => true,
+                (Some((end, _)), Some(lower)) => lower <= end,
+            };
+
+            if overlaps {
+                last.1 = match (last.1.take(), upper) {
+                    (Some(a), Some(b)) => Some(if (&b.0, b.1) > (&a.0, a.1) { b } else { a }),
+                    _ => None,
+                };
+                continue;
+            }
+        }
+
+        merged.push((lower, upper));
+    }
+
+    let (mut patched, mut unaffected): (Vec<String>, Vec<String>) = (vec![], vec![]);
+
+    if let Some((Some(first), _)) = merged.first() {
+        unaffected.push(format!("< {}", first));
+    }
+
+    for (index, (_, upper)) in merged.iter().enumerate() {
+        if let Some((end, inclusive)) = upper {
+            let lower = format!("{} {}", if *inclusive { ">" } else { ">=" }, end);
+
+            match merged.get(index + 1) {
+                Some((Some(next), _)) => patched.push(format!("{}, < {}", lower, next)),
+                _ => patched.push(lower),
+            }
+        }
+    }
+
+    Version {
+        patched: Some(patched),
+        unaffected: Some(unaffected),
+    }
+}
\ No newline at end of file
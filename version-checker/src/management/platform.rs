@@ -0,0 +1,220 @@
+/// The parts of a target triple which `cfg(...)` expressions can test
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub triple: String,
+    pub arch: String,
+    pub os: String,
+    pub vendor: String,
+    pub env: String,
+    pub endian: String,
+}
+
+impl Target {
+    pub fn from_triple(triple: &str) -> Target {
+        let parts = triple.split('-').collect::<Vec<&str>>();
+        let last = parts.last().cloned().unwrap_or_default();
+
+        Target {
+            triple: triple.to_string(),
+            arch: target_arch(triple),
+            // Bare metal targets have no operating system, while `wasm32-unknown-unknown` reports `unknown`
+            os: target_os(triple).unwrap_or(if parts.get(2) == Some(&"unknown") { "unknown".to_string() } else { "none".to_string() }),
+            vendor: if parts.len() >= 3 { parts[1].to_string() } else { "unknown".to_string() },
+            env: ["gnu", "msvc", "musl", "sgx", "uclibc", "newlib"].iter()
+                .find(|env| parts.len() >= 3 && last.starts_with(*env))
+                .map(|env| env.to_string())
+                .unwrap_or_default(),
+            endian: target_endian(triple),
+        }
+    }
+
+    pub fn family(&self) -> &str {
+        match self.os.as_str() {
+            "windows" => "windows",
+            "linux" | "android" | "macos" | "ios" | "freebsd" | "netbsd" | "openbsd" | "dragonfly" | "solaris" |
+            "illumos" | "fuchsia" | "redox" | "emscripten" | "haiku" => "unix",
+            _ if self.arch.starts_with("wasm") => "wasm",
+            _ => "",
+        }
+    }
+
+    pub fn pointer_width(&self) -> &str {
+        match self.arch.as_str() {
+            "x86_64" | "aarch64" | "riscv64" | "powerpc64" | "mips64" | "s390x" | "sparc64" | "wasm64" => "64",
+            "avr" | "msp430" => "16",
+            _ => "32",
+        }
+    }
+
+    /// Whether the key of a `[target.'...'.dependencies]` table applies to this target, either a `cfg(...)`
+    /// expression or a target triple. Expressions which can't be parsed never match
+    pub fn matches(&self, spec: &str) -> bool {
+        let spec = spec.trim();
+
+        if let Some(expression) = spec.strip_prefix("cfg(").and_then(|rest| rest.strip_suffix(')')) {
+            let tokens = tokenize(expression);
+            let mut position = 0;
+
+            match self.predicate(&tokens, &mut position) {
+                Some(result) if position == tokens.len() => result,
+                _ => false,
+            }
+        } else {
+            spec == self.triple
+        }
+    }
+
+    fn predicate(&self, tokens: &[Token], position: &mut usize) -> Option<bool> {
+        let name = match tokens.get(*position)? {
+            Token::Ident(name) => name.clone(),
+            _ => return None,
+        };
+        *position += 1;
+
+        match tokens.get(*position) {
+            Some(Token::Open) => {
+                *position += 1;
+                let mut results: Vec<bool> = vec![];
+
+                while tokens.get(*position) != Some(&Token::Close) {
+                    results.push(self.predicate(tokens, position)?);
+
+                    if tokens.get(*position) == Some(&Token::Comma) {
+                        *position += 1;
+                    } else if tokens.get(*position) != Some(&Token::Close) {
+                        return None;
+                    }
+                }
+                *position += 1;
+
+                match name.as_str() {
+                    "all" => Some(results.iter().all(|result| *result)),
+                    "any" => Some(results.iter().any(|result| *result)),
+                    "not" if results.len() == 1 => Some(!results[0]),
+                    _ => None,
+                }
+            }
+            Some(Token::Equals) => {
+                *position += 1;
+                let value = match tokens.get(*position)? {
+                    Token::Text(value) => value.clone(),
+                    _ => return None,
+                };
+                *position += 1;
+
+                Some(match name.as_str() {
+                    "target_os" => self.os == value,
+                    "target_arch" => self.arch == value,
+                    "target_vendor" => self.vendor == value,
+                    "target_env" => self.env == value,
+                    "target_family" => self.family() == value,
+                    "target_pointer_width" => self.pointer_width() == value,
+                    "target_endian" => self.endian == value,
+                    _ => false,
+                })
+            }
+            _ => Some(match name.as_str() {
+                "unix" => self.family() == "unix",
+                "windows" => self.family() == "windows",
+                _ => false,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Text(String),
+    Open,
+    Close,
+    Comma,
+    Equals,
+}
+
+fn tokenize(expression: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = vec![];
+    let mut characters = expression.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match character {
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            ',' => tokens.push(Token::Comma),
+            '=' => tokens.push(Token::Equals),
+            '"' => {
+                let mut text = String::new();
+                while let Some(next) = characters.next() {
+                    if next == '"' {
+                        break;
+                    }
+                    text.push(next);
+                }
+                tokens.push(Token::Text(text));
+            }
+            character if character.is_alphanumeric() || character == '_' => {
+                let mut ident = character.to_string();
+                while let Some(next) = characters.peek().cloned() {
+                    if !(next.is_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    ident.push(next);
+                    characters.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            _ => {}
+        }
+    }
+
+    tokens
+}
+
+/// The architecture named by a target triple, as `cfg(target_arch)` and advisories spell it
+pub fn target_arch(triple: &str) -> String {
+    match triple.split('-').next().unwrap_or_default() {
+        "i386" | "i586" | "i686" => "x86".to_string(),
+        "powerpc64le" => "powerpc64".to_string(),
+        "mipsel" | "mipsisa32r6" | "mipsisa32r6el" => "mips".to_string(),
+        "mips64el" | "mipsisa64r6" | "mipsisa64r6el" => "mips64".to_string(),
+        "sparcv9" => "sparc64".to_string(),
+        "aarch64_be" => "aarch64".to_string(),
+        arch if arch.starts_with("arm") || arch.starts_with("thumb") => "arm".to_string(),
+        arch if arch.starts_with("riscv64") => "riscv64".to_string(),
+        arch if arch.starts_with("riscv32") => "riscv32".to_string(),
+        arch => arch.to_string(),
+    }
+}
+
+/// The byte order of a target triple, big endian for architectures such as `s390x`, `powerpc64` and `mips` and for
+/// the `eb` or `_be` variants of little endian ones, little endian otherwise
+pub fn target_endian(triple: &str) -> String {
+    let arch = triple.split('-').next().unwrap_or_default();
+    let big = match arch {
+        "s390x" | "powerpc" | "powerpc64" | "mips" | "mips64" | "mipsisa32r6" | "mipsisa64r6" | "sparc" | "sparc64" |
+        "sparcv9" | "m68k" | "aarch64_be" => true,
+        arch => arch.starts_with("armeb") || arch.starts_with("thumbeb"),
+    };
+
+    if big { "big".to_string() } else { "little".to_string() }
+}
+
+/// The operating system named by a target triple, as `cfg(target_os)` and advisories spell it
+pub fn target_os(triple: &str) -> Option<String> {
+    let mut found: Option<String> = None;
+
+    for part in triple.split('-').skip(1) {
+        let os = match part {
+            "darwin" => Some("macos"),
+            "windows" | "linux" | "android" | "ios" | "freebsd" | "netbsd" | "openbsd" | "dragonfly" |
+            "solaris" | "illumos" | "fuchsia" | "redox" | "emscripten" | "wasi" | "haiku" => Some(part),
+            _ => None,
+        };
+
+        if let Some(os) = os {
+            found = Some(os.to_string());
+        }
+    }
+
+    found
+}
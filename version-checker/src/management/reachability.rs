@@ -0,0 +1,113 @@
+use std::fs::{read_dir, read_to_string};
+use std::path::Path;
+
+/// The Rust sources of the project being checked, used to look for uses of the functions named by advisories
+#[derive(Debug, Clone, Default)]
+pub struct SourceIndex {
+    pub files: Vec<(String, String)>,
+}
+
+impl SourceIndex {
+    pub fn new<P: AsRef<Path>>(root: P) -> SourceIndex {
+        let mut index = SourceIndex::default();
+        index.scan(root.as_ref(), root.as_ref());
+        index.files.sort();
+        index
+    }
+
+    fn scan(&mut self, root: &Path, dir: &Path) {
+        if let Ok(entries) = read_dir(dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let name = entry.file_name().to_string_lossy().to_string();
+
+                if path.is_dir() {
+                    if name != "target" && !name.starts_with('.') {
+                        self.scan(root, &path);
+                    }
+                } else if name.ends_with(".rs") {
+                    if let Ok(content) = read_to_string(&path) {
+                        let relative = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().to_string();
+                        self.files.push((relative, content));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Finds the first line which looks like it uses `item`, a path such as `smallvec::SmallVec::insert_many`.
+    /// Only files which use the crate, in a `use` or as the start of a path, are searched. A line counts when it has the full path,
+    /// `Type::method`, or a call of the method or function when the type or function is imported from the crate
+    pub fn find_reference(&self, item: &str) -> Option<(String, usize)> {
+        let segments = item.split("::").map(|segment| segment.replace('-', "_")).collect::<Vec<String>>();
+        let path = segments.join("::");
+        let crate_name = segments[0].as_str();
+        let last = segments[segments.len() - 1].as_str();
+        let parent = if segments.len() >= 3 { Some(segments[segments.len() - 2].as_str()) } else { None };
+
+        for (file, content) in &self.files {
+            let code = content.lines().enumerate().filter(|(_, line)| !line.trim_start().starts_with("//")).collect::<Vec<(usize, &str)>>();
+            let imports = code.iter().map(|(_, line)| *line).filter(|line| is_import(line) && contains_word(line, crate_name)).collect::<Vec<&str>>();
+
+            if imports.is_empty() && !code.iter().any(|(_, line)| starts_path(line, crate_name)) {
+                continue;
+            }
+
+            let imported = |name: &str| imports.iter().any(|line| contains_word(line, name) || line.contains("::*"));
+
+            for (index, line) in code.iter() {
+                if is_import(line) {
+                    continue;
+                }
+
+                let found = contains_word(line, path.as_str()) || match parent {
+                    Some(parent) => {
+                        contains_word(line, format!("{}::{}", parent, last).as_str()) || (imported(parent) && contains_method_call(line, last))
+                    }
+                    None => imported(last) && contains_word(line, last),
+                };
+
+                if found {
+                    return Some((file.clone(), index + 1));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+fn is_import(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("use ") || line.starts_with("pub use ") || line.starts_with("extern crate ")
+}
+
+/// Whether a path starts with the name, such as `smallvec::SmallVec::new()` for `smallvec`
+fn starts_path(line: &str, name: &str) -> bool {
+    line.match_indices(format!("{}::", name).as_str()).any(|(index, _)| {
+        !line[..index].chars().last().map_or(false, |before| is_identifier(before) || before == ':')
+    })
+}
+
+/// Whether the name is called as a method, such as `.insert_many(` or `.insert_many::<T>(`
+fn contains_method_call(line: &str, name: &str) -> bool {
+    line.match_indices(name).any(|(index, _)| {
+        let before = line[..index].chars().last();
+        let after = line[index + name.len()..].chars().next();
+
+        before == Some('.') && !after.map_or(false, is_identifier)
+    })
+}
+
+fn contains_word(line: &str, word: &str) -> bool {
+    line.match_indices(word).any(|(index, _)| {
+        let before = line[..index].chars().last();
+        let after = line[index + word.len()..].chars().next();
+
+        !before.map_or(false, is_identifier) && !after.map_or(false, is_identifier)
+    })
+}
+
+fn is_identifier(character: char) -> bool {
+    character.is_alphanumeric() || character == '_'
+}
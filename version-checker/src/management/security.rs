@@ -1,18 +1,49 @@
-use std::env::current_exe;
 use std::collections::HashMap;
-use std::fs::OpenOptions;
-use std::io::{Write, Read};
+use std::fs::{OpenOptions, read, read_dir, read_to_string, create_dir_all, rename, remove_file};
+use std::io::{Write, Read, Cursor};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use regex::Regex;
+use fs2::FileExt;
+use rayon::prelude::*;
 use reqwest::blocking::{Client, ClientBuilder};
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use zip::read::ZipArchive;
 
+use crate::management::cvss::{Cvss, Severity};
+use crate::management::osv;
+use crate::management::platform;
 use crate::utilities::errors::{Errors, VerificationError};
-use crate::utilities::serial::security::ParentalAdvisory;
+use crate::utilities::serial::config::{IgnoredAdvisory, AdvisorySource};
+use crate::utilities::serial::osv::OsvAdvisory;
+use crate::utilities::serial::security::{ParentalAdvisory, DatabaseCache, SNAPSHOT_SCHEMA};
 
+/// Prefixes the keys of advisories from the advisory-db's `rust` collection, which can't clash with crate names
+pub const TOOLCHAIN_PREFIX: &str = "rust/";
 
 pub struct SecurityDatabase {
     pub client: Client,
     pub advisories: HashMap<String, Vec<ParentalAdvisory>>,
+    pub fetched: u64,
+    pub max_age: u64,
+    pub offline: bool,
+    pub stale: bool,
+    pub denied_informational: Vec<String>,
+    pub show_withdrawn: bool,
+    pub target_os: String,
+    pub target_arch: String,
+    pub min_severity: Severity,
+    pub ignored: Vec<IgnoredAdvisory>,
+    pub sources: Vec<AdvisorySource>,
+    pub url: String,
+    pub pinned_sha256: Option<String>,
+    pub checksum_url: Option<String>,
+    pub cache_dir: Option<PathBuf>,
+    pub etag: Option<String>,
+    pub sha256: Option<String>,
 }
 
 impl SecurityDatabase {
@@ -20,91 +51,576 @@ impl SecurityDatabase {
         SecurityDatabase {
             client: ClientBuilder::new().user_agent("Kalavar Version Utility v1.0 <Thomas B. | tom.b.2k2@gmail.com>").build().unwrap(),
             advisories: HashMap::new(),
+            fetched: 0,
+            max_age: 24,
+            offline: false,
+            stale: false,
+            denied_informational: vec![],
+            show_withdrawn: false,
+            target_os: std::env::consts::OS.to_string(),
+            target_arch: std::env::consts::ARCH.to_string(),
+            min_severity: Severity::None,
+            ignored: vec![],
+            sources: vec![AdvisorySource::rustsec()],
+            url: "https://github.com/RustSec/advisory-db/archive/master.zip".to_string(),
+            pinned_sha256: None,
+            checksum_url: None,
+            cache_dir: None,
+            etag: None,
+            sha256: None,
         }
     }
 
+    /// Fills the database from each of the `sources` in turn, an advisory sharing an id or alias with one from an
+    /// earlier source is skipped. Every advisory records the name of the source it was read from
+    pub fn load(&mut self) -> Result<(), VerificationError> {
+        let mut merged: HashMap<String, Vec<ParentalAdvisory>> = HashMap::new();
+
+        for source in self.sources.clone() {
+            self.advisories = HashMap::new();
+
+            match (source.path.clone(), source.format.clone()) {
+                (Some(path), Some(format)) if format == "osv" => self.read_osv_directory(Path::new(path.as_str()))?,
+                (Some(path), _) => self.load_local(path.as_str())?,
+                (None, _) => self.load_remote()?,
+            }
+
+            let loaded = std::mem::replace(&mut self.advisories, merged);
+            for (crate_name, list) in loaded {
+                for mut advisory in list {
+                    advisory.source = Some(source.name.clone());
+                    self.insert(crate_name.clone(), advisory);
+                }
+            }
+            merged = std::mem::take(&mut self.advisories);
+        }
+
+        self.advisories = merged;
+        self.sort();
+        Ok(())
+    }
+
+    /// Fills the database from the on-disk cache when it is younger than `max_age` hours, otherwise downloads
+    /// a fresh copy of the RustSec database, falling back to the cache if the download fails
+    fn load_remote(&mut self) -> Result<(), VerificationError> {
+        let lock = OpenOptions::new().write(true).create(true).open(self.storage_path("security.lock")?)
+            .map_err(|_| VerificationError::new(Errors::DBNotWriteable))?;
+
+        // Held until the cache has been read or replaced, so concurrent runs sharing a cache don't download over each other
+        lock.lock_exclusive().map_err(|_| VerificationError::new(Errors::DBNotWriteable))?;
+        let result = self.load_remote_locked();
+        let _ = lock.unlock();
+
+        result
+    }
+
+    fn load_remote_locked(&mut self) -> Result<(), VerificationError> {
+        // A snapshot of another archive is treated as missing, so it is neither used nor fallen back on
+        let cache = self.read_cache().filter(|cached| self.is_cache_for(cached));
+
+        if let Some(cached) = cache {
+            let age = now().saturating_sub(cached.fetched);
+
+            // Saturating, so a very large `--db-max-age` keeps the cache forever rather than overflowing
+            let max_age = self.max_age.saturating_mul(3600);
+
+            if self.offline || age < max_age {
+                self.stale = age >= max_age;
+                self.fetched = cached.fetched;
+                self.advisories = cached.advisories;
+                return Ok(());
+            }
+
+            // The cached copy is kept in place so an unchanged archive, answered with a 304, doesn't need parsing again
+            self.etag = cached.etag.clone();
+            self.sha256 = cached.sha256.clone();
+            self.advisories = cached.advisories.clone();
+
+            return match self.update() {
+                Ok(()) => {
+                    self.write_cache();
+                    Ok(())
+                }
+                Err(error) if matches!(error.inner, Errors::DBIntegrityMismatch) => Err(error),
+                Err(_) => {
+                    self.stale = true;
+                    self.fetched = cached.fetched;
+                    self.etag = cached.etag;
+                    self.sha256 = cached.sha256;
+                    self.advisories = cached.advisories;
+                    Ok(())
+                }
+            };
+        }
+
+        if self.offline {
+            return Err(VerificationError::new(Errors::DBCacheUnavailable));
+        }
+
+        self.update()?;
+        self.write_cache();
+        Ok(())
+    }
+
+    /// The number of whole days since the advisories were downloaded
+    pub fn age_days(&self) -> u64 {
+        now().saturating_sub(self.fetched) / 86400
+    }
+
     pub fn update(&mut self) -> Result<(), VerificationError> {
+        if let Some(archive) = self.download()? {
+            self.advisories = HashMap::new();
+            self.read_archive(archive.as_slice())?;
+        }
+
+        self.fetched = now();
+        self.stale = false;
+        Ok(())
+    }
+
+    /// Downloads and verifies the advisory archive, returning nothing when the server reports that the
+    /// archive matching the stored ETag hasn't changed
+    fn download(&mut self) -> Result<Option<Vec<u8>>, VerificationError> {
         let mut dirbytes: Vec<u8> = Vec::new();
-        let _ = self.client.get("https://github.com/RustSec/advisory-db/archive/master.zip").send().unwrap().read_to_end(&mut dirbytes);
+        let mut request = self.client.get(self.url.as_str());
+
+        if let (Some(etag), false) = (self.etag.clone(), self.advisories.is_empty()) {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
 
-        let exe_dir = current_exe().unwrap().as_os_str().to_str().unwrap().to_string();
+        let mut body = request.send().map_err(|_| VerificationError::new(Errors::DBUpdateFailed))?;
 
-        let mut home_dir_vec = exe_dir.split('/').collect::<Vec<&str>>();
-        home_dir_vec.pop();
-        let path = format!("{}/security.zip", home_dir_vec.join("/"));
+        if body.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+
+        let etag = body.headers().get(ETAG).and_then(|value| value.to_str().ok()).map(|value| value.to_string());
 
-        let e = OpenOptions::new().write(true).read(true).create(true).open(path.as_str());
+        if !body.status().is_success() || body.read_to_end(&mut dirbytes).is_err() {
+            return Err(VerificationError::new(Errors::DBUpdateFailed));
+        }
 
-        return if e.is_err() {
-            Err(VerificationError::new(Errors::DBUpdateFailed))
+        self.sha256 = Some(self.verify(dirbytes.as_slice())?);
+        self.etag = etag;
+        Ok(Some(dirbytes))
+    }
+
+    /// Compares the SHA-256 of a downloaded archive with the pinned hash and the checksum file, when either is set,
+    /// returning the digest of the archive
+    pub fn verify(&self, archive: &[u8]) -> Result<String, VerificationError> {
+        let digest = format!("{:x}", Sha256::digest(archive));
+
+        if let Some(pinned) = self.pinned_sha256.clone() {
+            if !pinned.trim().eq_ignore_ascii_case(digest.as_str()) {
+                return Err(VerificationError::new(Errors::DBIntegrityMismatch));
+            }
+        }
+
+        if let Some(url) = self.checksum_url.clone() {
+            let mut content = String::new();
+            let response = self.client.get(url.as_str()).send();
+
+            if let Ok(mut body) = response {
+                if !body.status().is_success() || body.read_to_string(&mut content).is_err() {
+                    return Err(VerificationError::new(Errors::DBUpdateFailed));
+                }
+            } else {
+                return Err(VerificationError::new(Errors::DBUpdateFailed));
+            }
+
+            // Checksum files follow the sha256sum format, the hash comes first and is optionally followed by the file name
+            let published = content.split_whitespace().next().unwrap_or("");
+
+            if !published.eq_ignore_ascii_case(digest.as_str()) {
+                return Err(VerificationError::new(Errors::DBIntegrityMismatch));
+            }
+        }
+
+        Ok(digest)
+    }
+
+    /// Whether a snapshot was made from the archive this database is set to download, the same URL and,
+    /// when a hash is pinned, an archive with that digest
+    pub fn is_cache_for(&self, cached: &DatabaseCache) -> bool {
+        let pinned = self.pinned_sha256.as_ref().map_or(true, |pinned| {
+            cached.sha256.as_ref().map_or(false, |digest| pinned.trim().eq_ignore_ascii_case(digest.as_str()))
+        });
+
+        cached.url == self.url && pinned
+    }
+
+    /// Reads the advisories from a local copy of the advisory-db, either a checkout of the repository or a zip of it.
+    /// A copy without any advisories is most likely the wrong path or layout, so it is an error rather than a clean report
+    pub fn load_local(&mut self, path: &str) -> Result<(), VerificationError> {
+        let location = Path::new(path);
+
+        self.advisories = HashMap::new();
+
+        if location.is_dir() {
+            self.read_directory(location)?;
+        } else if let Ok(archive) = read(location) {
+            self.read_archive(archive.as_slice())?;
         } else {
-            let mut db = e.unwrap();
-            let frontmatter_matcher = Regex::new(r#"`{3}toml(.*[\n])*`{3}"#).unwrap();
-            let frontmatter_replacer = Regex::new(r#"`{3}\n|`{3}toml\n"#).unwrap();
-            let write_status = db.write_all(dirbytes.as_slice());
-            if write_status.is_ok() {
-                let _ = db.flush();
-
-                let repo = zip::read::ZipArchive::new(&mut db);
-
-                if let Ok(zipped) = repo {
-                    let mut file_clone = OpenOptions::new().write(true).read(true).create(true).open(path.as_str()).unwrap();
-                    let mut repo_clone = zip::read::ZipArchive::new(&mut file_clone).unwrap();
-                    let base = "advisory-db-master";
-                    let paths: Vec<&str> = zipped.file_names().collect::<Vec<&str>>().clone();
-                    for path in paths {
-                        if path.contains(base) && path.ends_with(".md") {
-                            let hostdir = path.split(base).collect::<Vec<&str>>();
-                            let parts = hostdir[1].split('/').collect::<Vec<&str>>();
-                            if parts.len() > 1 {
-                                let file = parts[1];
-                                if !file.is_empty() {
-                                    let advice = repo_clone.by_name(path);
-                                    if advice.is_ok() {
-                                        let mut handle = advice.unwrap();
-                                        let mut bytes: Vec<u8> = vec!();
-
-                                        let read_result = handle.read_to_end(&mut bytes);
-
-                                        if read_result.is_ok() {
-                                            let text = String::from_utf8(bytes).unwrap();
-                                            let frm_cat = frontmatter_matcher.captures(text.as_str());
-                                            if frm_cat.is_some() {
-                                                let frontmatter = frontmatter_replacer.replace_all(frm_cat.unwrap().get(0).unwrap().as_str(), "").to_string().replace("```", "");
-                                                let body = frontmatter_matcher.replace(text.as_str(), "").to_string();
-
-                                                let parse_result: Result<ParentalAdvisory, toml::de::Error> = toml::from_str(frontmatter.as_str());
-
-                                                if parse_result.is_ok() {
-                                                    let mut advisory = parse_result.unwrap();
-                                                    let cloned = advisory.clone();
-                                                    advisory.body = Some(body);
-
-                                                    let crate_name = cloned.advisory.unwrap().package.unwrap();
-
-                                                    if self.advisories.contains_key(crate_name.as_str()) {
-                                                        self.advisories.get_mut(crate_name.as_str()).unwrap();
-                                                    } else {
-                                                        let vector: Vec<ParentalAdvisory> = vec![advisory];
-                                                        self.advisories.insert(crate_name, vector);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    } else {
-                                        println!("Err: Unable to locate file in zip folder");
-                                    }
-                                }
-                            }
-                        }
+            return Err(VerificationError::new(Errors::DBUnreadable));
+        }
+
+        if self.advisories.is_empty() {
+            return Err(VerificationError::new(Errors::DBUnreadable));
+        }
+        Ok(())
+    }
+
+    /// Reads the advisories from an in-memory zip of the advisory-db, each thread decompresses and parses
+    /// entries using its own handle on the archive
+    pub fn read_archive(&mut self, archive: &[u8]) -> Result<(), VerificationError> {
+        let zipped = ZipArchive::new(Cursor::new(archive)).map_err(|_| VerificationError::new(Errors::DBUnreadable))?;
+        let paths: Vec<String> = zipped.file_names().filter(|name| is_advisory_path(name)).map(|name| name.to_string()).collect();
+
+        let parsed: Vec<(String, ParentalAdvisory)> = paths.par_iter().map_init(|| zipped.clone(), |handle, path| {
+            let mut text = String::new();
+            let mut entry = handle.by_name(path.as_str()).ok()?;
+
+            entry.read_to_string(&mut text).ok()?;
+            let advisory = parse_advisory(text.as_str())?;
+            Some((advisory_key(path.as_str(), &advisory)?, advisory))
+        }).flatten().collect();
+
+        self.insert_all(parsed);
+        Ok(())
+    }
+
+    /// Reads a checkout of the advisory-db, the `crates` collection is required and the `rust` one is optional
+    fn read_directory(&mut self, root: &Path) -> Result<(), VerificationError> {
+        let crates = read_dir(root.join("crates")).map_err(|_| VerificationError::new(Errors::DBUnreadable))?;
+        let packages = crates.chain(read_dir(root.join("rust")).into_iter().flatten());
+        let mut paths: Vec<PathBuf> = vec![];
+
+        for package in packages.flatten() {
+            if let Ok(files) = read_dir(package.path()) {
+                for file in files.flatten() {
+                    let path = file.path();
+
+                    if path.extension().map_or(false, |extension| extension == "md") {
+                        paths.push(path);
                     }
-                    Ok(())
-                } else {
-                    Err(VerificationError::new(Errors::DBUnreadable))
                 }
-            } else {
-                Err(VerificationError::new(Errors::DBUnreadable))
             }
+        }
+
+        let parsed: Vec<(String, ParentalAdvisory)> = paths.par_iter()
+            .filter_map(|path| {
+                let advisory = parse_advisory(read_to_string(path).ok()?.as_str())?;
+                let relative = path.strip_prefix(root).ok()?.to_string_lossy().replace('\\', "/");
+                Some((advisory_key(relative.as_str(), &advisory)?, advisory))
+            })
+            .collect();
+
+        self.insert_all(parsed);
+        Ok(())
+    }
+
+    /// Reads every OSV JSON document in a directory. A document which can't be parsed stops the load, as skipping it
+    /// would leave the crates it covers looking clean
+    pub fn read_osv_directory(&mut self, root: &Path) -> Result<(), VerificationError> {
+        let files = read_dir(root).map_err(|_| VerificationError::new(Errors::DBUnreadable))?;
+
+        for file in files.flatten() {
+            let path = file.path();
+
+            if path.extension().map_or(false, |extension| extension == "json") {
+                let invalid = |reason: String| VerificationError::new(Errors::OsvDocumentInvalid(format!("{}: {}", path.display(), reason)));
+                let text = read_to_string(&path).map_err(|error| invalid(error.to_string()))?;
+                let document = serde_json::from_str::<OsvAdvisory>(text.as_str()).map_err(|error| invalid(error.to_string()))?;
+
+                for advisory in osv::convert(document) {
+                    if let Some(crate_name) = advisory.advisory.clone().and_then(|details| details.package) {
+                        self.insert(crate_name, advisory);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_all(&mut self, advisories: Vec<(String, ParentalAdvisory)>) {
+        for (key, advisory) in advisories {
+            self.insert(key, advisory);
+        }
+        self.sort();
+    }
+
+    /// The advisories against the Rust toolchain and standard library, alongside the component each is for
+    pub fn toolchain_advisories(&self) -> Vec<(String, ParentalAdvisory)> {
+        let mut found: Vec<(String, ParentalAdvisory)> = vec![];
+
+        for (key, list) in self.advisories.iter() {
+            if key.starts_with(TOOLCHAIN_PREFIX) {
+                for advisory in list {
+                    found.push((key[TOOLCHAIN_PREFIX.len()..].to_string(), advisory.clone()));
+                }
+            }
+        }
+
+        found.sort_by_key(|(_, advisory)| advisory.advisory.clone().and_then(|details| details.date));
+        found
+    }
+
+    /// Informational advisories (unmaintained, unsound, notice) only count as vulnerabilities when their kind is denied
+    pub fn is_vulnerability(&self, advisory: &ParentalAdvisory) -> bool {
+        return if let Some(kind) = advisory.advisory.clone().and_then(|details| details.informational) {
+            self.denied_informational.contains(&kind)
+        } else {
+            true
+        };
+    }
+
+    /// Finds the ignore entry matching the id or one of the aliases of an advisory, entries stop matching once their `until` date has passed
+    pub fn ignore_entry(&self, advisory: &ParentalAdvisory) -> Option<IgnoredAdvisory> {
+        let ids = identifiers(advisory);
+        let date = today();
+
+        self.ignored.iter().find(|entry| {
+            ids.contains(&entry.id) && entry.until.as_ref().map_or(true, |until| until.as_str() >= date.as_str())
+        }).cloned()
+    }
+
+    /// Advisories without a CVSS score are always treated as meeting the minimum severity
+    pub fn meets_severity(&self, advisory: &ParentalAdvisory) -> bool {
+        return if let Some(cvss) = Cvss::of(advisory) {
+            cvss.severity >= self.min_severity
+        } else {
+            true
+        };
+    }
+
+    /// Checks the `os` and `arch` lists of an advisory against the target, an empty or missing list applies to every platform
+    pub fn is_platform_affected(&self, advisory: &ParentalAdvisory) -> bool {
+        return if let Some(affected) = advisory.affected.clone() {
+            let os = affected.os.unwrap_or_default();
+            let arch = affected.arch.unwrap_or_default();
+
+            (os.is_empty() || os.contains(&self.target_os)) && (arch.is_empty() || arch.contains(&self.target_arch))
+        } else {
+            true
         };
     }
+
+    /// Sets the target platform from a target triple such as `x86_64-pc-windows-msvc`, both the architecture and the
+    /// operating system are replaced, so a bare metal triple never keeps the host's operating system
+    pub fn set_target(&mut self, triple: &str) {
+        let target = platform::Target::from_triple(triple);
+
+        self.target_arch = target.arch;
+        self.target_os = target.os;
+    }
+
+    /// Adds an advisory to the list for its crate, skipping it if an advisory sharing its id or one of its aliases is already stored
+    pub fn insert(&mut self, crate_name: String, advisory: ParentalAdvisory) {
+        let list = self.advisories.entry(crate_name).or_insert_with(Vec::new);
+        let ids = identifiers(&advisory);
+
+        if ids.is_empty() || !list.iter().any(|existing| identifiers(existing).iter().any(|id| ids.contains(id))) {
+            list.push(advisory);
+        }
+    }
+
+    /// Orders the advisories of every crate by their date, oldest first
+    pub fn sort(&mut self) {
+        for list in self.advisories.values_mut() {
+            list.sort_by_key(|advisory| advisory.advisory.clone().and_then(|details| details.date));
+        }
+    }
+
+    /// Finds the advisory with the given id or alias, ignoring case
+    pub fn find(&self, id: &str) -> Option<ParentalAdvisory> {
+        self.advisories.values()
+            .flatten()
+            .find(|advisory| identifiers(advisory).iter().any(|known| known.eq_ignore_ascii_case(id)))
+            .cloned()
+    }
+
+    /// Finds every advisory with the id or alias, an OSV document affecting several crates is stored as one advisory for each of them
+    pub fn find_all(&self, id: &str) -> Vec<ParentalAdvisory> {
+        let mut found: Vec<ParentalAdvisory> = self.advisories.values()
+            .flatten()
+            .filter(|advisory| identifiers(advisory).iter().any(|known| known.eq_ignore_ascii_case(id)))
+            .cloned()
+            .collect();
+        found.sort_by_key(|advisory| advisory.advisory.clone().and_then(|details| details.package));
+        found
+    }
+
+    /// Finds every advisory whose id, aliases, package, keywords or categories contain the keyword, ignoring case
+    pub fn search(&self, keyword: &str) -> Vec<ParentalAdvisory> {
+        let keyword = keyword.to_lowercase();
+        let mut found: Vec<ParentalAdvisory> = self.advisories.values()
+            .flatten()
+            .filter(|advisory| {
+                let details = match advisory.advisory.clone() {
+                    Some(details) => details,
+                    None => return false,
+                };
+                let mut terms = identifiers(advisory);
+                terms.extend(details.package);
+                terms.extend(details.keywords.unwrap_or_default());
+                terms.extend(details.categories.unwrap_or_default());
+
+                terms.iter().any(|term| term.to_lowercase().contains(keyword.as_str()))
+            })
+            .cloned()
+            .collect();
+
+        found.sort_by_key(|advisory| advisory.advisory.clone().and_then(|details| details.date));
+        found
+    }
+
+    /// Loads the binary snapshot of the database, snapshots written with a different schema are ignored so they get rebuilt
+    pub fn read_cache(&self) -> Option<DatabaseCache> {
+        let content = read(self.storage_path("security.bin").ok()?).ok()?;
+        let schema: u32 = bincode::deserialize(content.get(0..4)?).ok()?;
+
+        if schema != SNAPSHOT_SCHEMA {
+            return None;
+        }
+
+        bincode::deserialize(content.as_slice()).ok()
+    }
+
+    pub fn write_cache(&self) {
+        let cache = DatabaseCache {
+            schema: SNAPSHOT_SCHEMA,
+            fetched: self.fetched,
+            etag: self.etag.clone(),
+            url: self.url.clone(),
+            sha256: self.sha256.clone(),
+            advisories: self.advisories.clone(),
+        };
+
+        if let Ok(content) = bincode::serialize(&cache) {
+            let _ = self.write_atomic("security.bin", content.as_slice());
+        }
+    }
+
+    /// Builds the path of a file in the cache directory, `cache_dir` when it is set, otherwise the user's cache
+    /// directory such as `$XDG_CACHE_HOME/version-checker`
+    fn storage_path(&self, name: &str) -> Result<PathBuf, VerificationError> {
+        let directory = self.cache_dir.clone()
+            .or_else(|| dirs::cache_dir().map(|cache| cache.join("version-checker")))
+            .ok_or(VerificationError::new(Errors::DBNotWriteable))?;
+
+        create_dir_all(&directory).map_err(|_| VerificationError::new(Errors::DBNotWriteable))?;
+        Ok(directory.join(name))
+    }
+
+    /// Writes to a temporary file which is then renamed over the target, so readers never see a partly written file
+    fn write_atomic(&self, name: &str, content: &[u8]) -> Result<(), VerificationError> {
+        let target = self.storage_path(name)?;
+        let temporary = self.storage_path(format!("{}.{}.tmp", name, process::id()).as_str())?;
+
+        let written = OpenOptions::new().write(true).create(true).truncate(true).open(&temporary)
+            .and_then(|mut handle| handle.write_all(content).and_then(|_| handle.sync_all()))
+            .and_then(|_| rename(&temporary, &target));
+
+        if written.is_err() {
+            let _ = remove_file(&temporary);
+            return Err(VerificationError::new(Errors::DBNotWriteable));
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the TOML frontmatter of an advisory file, held in a ```` ```toml ```` fenced block,
+/// keeping the markdown around it as the body
+pub fn parse_advisory(text: &str) -> Option<ParentalAdvisory> {
+    let (frontmatter, body) = split_frontmatter(text)?;
+    let mut advisory: ParentalAdvisory = toml::from_str(frontmatter.as_str()).ok()?;
+
+    advisory.body = Some(body);
+    Some(advisory)
+}
+
+/// Splits the first `toml` fenced block out of a markdown document, returning its content and the remaining markdown.
+/// The block closes at the first line made only of at least as many backticks as opened it
+fn split_frontmatter(text: &str) -> Option<(String, String)> {
+    let (mut before, mut inside, mut after): (Vec<&str>, Vec<&str>, Vec<&str>) = (vec![], vec![], vec![]);
+    let mut fence: Option<usize> = None;
+    let mut closed = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let ticks = trimmed.chars().take_while(|character| *character == '`').count();
+
+        if closed {
+            after.push(line);
+        } else if let Some(opening) = fence {
+            if ticks >= opening && ticks == trimmed.len() {
+                closed = true;
+            } else {
+                inside.push(line);
+            }
+        } else if ticks >= 3 && trimmed[ticks..].trim() == "toml" {
+            fence = Some(ticks);
+        } else {
+            before.push(line);
+        }
+    }
+
+    if !closed {
+        return None;
+    }
+
+    before.extend(after);
+    Some((inside.join("\n"), before.join("\n")))
+}
+
+/// Advisories for crates live at `crates/<name>/RUSTSEC-*.md` within the advisory-db,
+/// and those for the toolchain at `rust/<component>/*.md`
+fn is_advisory_path(path: &str) -> bool {
+    let parts = path.split('/').collect::<Vec<&str>>();
+    path.ends_with(".md") && parts.len() >= 3 && (parts[parts.len() - 3] == "crates" || parts[parts.len() - 3] == "rust")
+}
+
+/// The key an advisory is stored under, its crate name, or its component behind `TOOLCHAIN_PREFIX` when it is
+/// from the `rust` collection so that toolchain components never mix with crates of the same name
+fn advisory_key(path: &str, advisory: &ParentalAdvisory) -> Option<String> {
+    let parts = path.split('/').collect::<Vec<&str>>();
+    let package = advisory.advisory.clone().and_then(|details| details.package)?;
+
+    if parts.len() >= 3 && parts[parts.len() - 3] == "rust" {
+        Some(format!("{}{}", TOOLCHAIN_PREFIX, package))
+    } else {
+        Some(package)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+}
+
+/// Today's date as `YYYY-MM-DD`, in UTC
+fn today() -> String {
+    let days = (now() / 86400) as i64 + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The id of an advisory followed by its aliases
+fn identifiers(advisory: &ParentalAdvisory) -> Vec<String> {
+    let mut ids: Vec<String> = vec![];
+
+    if let Some(details) = advisory.advisory.clone() {
+        ids.extend(details.id);
+        ids.extend(details.aliases.unwrap_or_default());
+    }
+
+    ids
 }
\ No newline at end of file
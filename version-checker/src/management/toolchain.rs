@@ -0,0 +1,64 @@
+use std::fs::read_to_string;
+use std::path::Path;
+use std::process::Command;
+
+use crate::management::crates_io::{Version, is_affected};
+use crate::management::security::SecurityDatabase;
+use crate::utilities::serial::security::ParentalAdvisory;
+
+/// The Rust version the project is built with, taken from a `rust-toolchain.toml` or `rust-toolchain` file which pins
+/// a version, otherwise from `rustc --version` run in the project so rustup picks the project's toolchain.
+/// Pre-release suffixes such as `-nightly` are dropped
+pub fn local_version(project_root: &Path) -> Option<semver::Version> {
+    pinned_version(project_root).or_else(|| {
+        let output = Command::new("rustc").arg("--version").current_dir(project_root).output().ok()?;
+        let text = String::from_utf8_lossy(output.stdout.as_slice()).to_string();
+
+        // Such as `rustc 1.52.1 (9bc8c42bb 2021-05-09)`
+        parse_version(text.split_whitespace().nth(1)?)
+    })
+}
+
+fn pinned_version(project_root: &Path) -> Option<semver::Version> {
+    let content = read_to_string(project_root.join("rust-toolchain.toml"))
+        .or_else(|_| read_to_string(project_root.join("rust-toolchain")))
+        .ok()?;
+
+    // The older `rust-toolchain` file may hold just the channel rather than TOML
+    let channel = match toml::from_str::<toml::Value>(content.as_str()) {
+        Ok(value) => value.get("toolchain")?.get("channel")?.as_str()?.to_string(),
+        Err(_) => content.trim().to_string(),
+    };
+
+    parse_version(channel.as_str())
+}
+
+/// Parses versions such as `1.52`, `1.52.1` or `1.54.0-nightly`, channel names such as `stable` give nothing
+fn parse_version(text: &str) -> Option<semver::Version> {
+    let release = text.split('-').next()?;
+    let mut pieces = release.split('.').collect::<Vec<&str>>();
+
+    while pieces.len() < 3 {
+        pieces.push("0");
+    }
+
+    semver::Version::parse(pieces.join(".").as_str()).ok()
+}
+
+/// The toolchain advisories affecting a version of Rust, skipping withdrawn and ignored ones, those for other platforms
+/// and those below the minimum severity. Informational advisories are kept, only `is_vulnerability` ones should fail a run
+pub fn affecting_advisories(db: &SecurityDatabase, version: &semver::Version) -> Vec<(String, ParentalAdvisory)> {
+    let local = Version {
+        is_semver: true,
+        is_provided: true,
+        prefixes: None,
+        semver: Some(version.clone()),
+        normal: None,
+    };
+
+    db.toolchain_advisories().into_iter().filter(|(_, advisory)| {
+        let withdrawn = advisory.advisory.clone().and_then(|details| details.withdrawn).is_some();
+
+        !withdrawn && db.ignore_entry(advisory).is_none() && db.is_platform_affected(advisory) && db.meets_severity(advisory) && is_affected(advisory, &local)
+    }).collect()
+}
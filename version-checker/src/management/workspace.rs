@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use toml::Value;
+
+use crate::utilities::errors::{Errors, VerificationError};
+
+/// A manifest read as plain TOML, keeping keys such as `workspace = true` which `cargo_toml` doesn't know about
+pub fn read_manifest(path: &Path) -> Result<Value, VerificationError> {
+    let content = read_to_string(path).map_err(|_| VerificationError::new(Errors::CrateFileNotFound))?;
+    toml::from_str(content.as_str()).map_err(|_| VerificationError::new(Errors::CrateFileNotFound))
+}
+
+/// The manifests of every member of the workspace a root manifest declares, with the root first when it is also a package.
+/// Members are expanded from the `members` globs, leaving out any matched by `exclude`. Gives nothing when there's no `[workspace]`
+pub fn members(root_manifest: &Path, raw: &Value) -> Option<Vec<PathBuf>> {
+    let workspace = raw.get("workspace")?;
+    let root = root_manifest.parent().unwrap_or(Path::new("."));
+    let excluded: Vec<PathBuf> = expand(root, workspace.get("exclude"));
+    let mut members: Vec<PathBuf> = vec![];
+
+    if raw.get("package").is_some() {
+        members.push(root_manifest.to_path_buf());
+    }
+
+    for directory in expand(root, workspace.get("members")) {
+        let manifest = directory.join("Cargo.toml");
+
+        if manifest.exists() && !excluded.contains(&directory) && !members.contains(&manifest) {
+            members.push(manifest);
+        }
+    }
+
+    Some(members)
+}
+
+/// The directories matched by a list of glob patterns relative to the workspace root
+fn expand(root: &Path, patterns: Option<&Value>) -> Vec<PathBuf> {
+    let mut directories: Vec<PathBuf> = vec![];
+
+    for pattern in patterns.and_then(|value| value.as_array()).cloned().unwrap_or_default() {
+        if let Some(pattern) = pattern.as_str() {
+            let full = root.join(pattern).to_string_lossy().to_string();
+
+            if let Ok(paths) = glob::glob(full.as_str()) {
+                let mut matched: Vec<PathBuf> = paths.flatten().filter(|path| path.is_dir()).collect();
+                matched.sort();
+                directories.extend(matched);
+            }
+        }
+    }
+
+    directories
+}
+
+/// Finds the workspace a member manifest belongs to by looking for a manifest with a `[workspace]` in the directories above it
+pub fn find_root(manifest: &Path) -> Option<Value> {
+    root_manifest(manifest).and_then(|root| read_manifest(root.as_path()).ok())
+}
+
+/// The path of the workspace root manifest above a member manifest, see `find_root`
+pub fn root_manifest(manifest: &Path) -> Option<PathBuf> {
+    let mut directory = manifest.canonicalize().ok()?.parent()?.parent().map(|parent| parent.to_path_buf());
+
+    while let Some(current) = directory {
+        let candidate = current.join("Cargo.toml");
+
+        if candidate.exists() {
+            if let Ok(raw) = read_manifest(candidate.as_path()) {
+                if raw.get("workspace").is_some() {
+                    return Some(candidate);
+                }
+            }
+        }
+        directory = current.parent().map(|parent| parent.to_path_buf());
+    }
+
+    None
+}
+
+/// The `[workspace.dependencies]` table of a workspace root, which members inherit from with `workspace = true`
+pub fn shared_dependencies(raw: &Value) -> HashMap<String, Value> {
+    raw.get("workspace")
+        .and_then(|workspace| workspace.get("dependencies"))
+        .and_then(|dependencies| dependencies.as_table())
+        .map(|table| table.iter().map(|(name, value)| (name.clone(), value.clone())).collect())
+        .unwrap_or_default()
+}
+
+/// Resolves a dependency written as `name = { workspace = true }` to the one declared by the workspace, keeping any
+/// other keys the member sets such as `features` or `optional`. Other dependencies are returned as they are
+pub fn inherit(name: &str, dependency: &Value, shared: &HashMap<String, Value>) -> Value {
+    let inherits = dependency.get("workspace").and_then(|value| value.as_bool()).unwrap_or(false);
+
+    if !inherits {
+        return dependency.clone();
+    }
+
+    let mut resolved = match shared.get(name) {
+        Some(Value::String(version)) => {
+            let mut table = toml::value::Table::new();
+            table.insert("version".to_string(), Value::String(version.clone()));
+            table
+        }
+        Some(Value::Table(table)) => table.clone(),
+        _ => toml::value::Table::new(),
+    };
+
+    if let Some(table) = dependency.as_table() {
+        for (key, value) in table {
+            if key != "workspace" {
+                resolved.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    Value::Table(resolved)
+}
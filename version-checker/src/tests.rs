@@ -1,8 +1,30 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Cursor, Read, Write};
+use std::time::Instant;
+
+use regex::Regex;
+use zip::read::ZipArchive;
+use zip::write::{FileOptions, ZipWriter};
+
 use crate::utilities::terminal::output::OutputManager;
+use crate::utilities::terminal::markdown;
 use crate::management::{
-    security::SecurityDatabase,
-    crates_io::CratesIOManager,
+    cvss::{Cvss, Severity},
+    osv::convert_ranges,
+    reachability::SourceIndex,
+    security::{SecurityDatabase, parse_advisory},
+    toolchain,
+    lockfile,
+    workspace,
+    crates_io::{CratesIOManager, Dependency, DependencyKind, Version, is_affected, manifest_entries, locked_dependencies},
+    platform::Target,
 };
+use crate::utilities::serial::osv::OsvRange;
+use crate::utilities::serial::lockfile::Lockfile;
+use crate::utilities::serial::config::{Config, IgnoredAdvisory};
+use crate::utilities::serial::security::{ParentalAdvisory, Advisory, Version as AdvisoryVersions, DatabaseCache, SNAPSHOT_SCHEMA};
+use crate::utilities::errors::{Errors, VerificationError};
 
 #[test]
 fn test_db_fetch() {
@@ -25,4 +47,594 @@ fn test_manifest_parser() {
         println!("{} - semver: {} - {}", dependency.name, dependency.version.is_semver, dependency.version);
         println!("{:#?}", dependency.version);
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_advisory_ranges() {
+    let remote = Version {
+        is_semver: false,
+        is_provided: false,
+        prefixes: None,
+        semver: None,
+        normal: None,
+    };
+    let advisory = ParentalAdvisory {
+        body: None,
+        source: None,
+        advisory: None,
+        versions: Some(AdvisoryVersions {
+            patched: Some(vec![">= 1.3.0".to_string(), ">= 1.2.4, < 1.3.0".to_string()]),
+            unaffected: Some(vec!["< 1.0.0".to_string()]),
+        }),
+        affected: None,
+    };
+
+    assert!(is_affected(&advisory, &Dependency::new("example", "1.2.3", remote.clone()).version));
+    assert!(!is_affected(&advisory, &Dependency::new("example", "1.2.5", remote.clone()).version));
+    assert!(!is_affected(&advisory, &Dependency::new("example", "1.4.0", remote.clone()).version));
+    assert!(!is_affected(&advisory, &Dependency::new("example", "0.9.0", remote).version));
+}
+
+#[test]
+fn test_advisory_check() {
+    let visual_manager = OutputManager::new(0, 112);
+    let remote = Version {
+        is_semver: false,
+        is_provided: false,
+        prefixes: None,
+        semver: None,
+        normal: None,
+    };
+    let advisory = ParentalAdvisory {
+        body: None,
+        source: None,
+        advisory: Some(Advisory { package: Some("example".to_string()), ..Advisory::default() }),
+        versions: Some(AdvisoryVersions {
+            patched: Some(vec![">= 1.2.4".to_string()]),
+            unaffected: None,
+        }),
+        affected: None,
+    };
+    let check = |version: &str| visual_manager.render_advisory_check("RUSTSEC-2021-0001", &advisory, &[Dependency::new("example", version, remote.clone())]);
+
+    assert!(check("1.2.3"));
+    assert!(!check("1.2.4"));
+    assert!(!check(""));
+    assert!(!check("branch-main"));
+
+    // An OSV document affecting several crates is checked against each of them
+    let mut db = SecurityDatabase::new();
+    for package in &["example", "example-derive"] {
+        let mut shared = advisory.clone();
+        shared.advisory = Some(Advisory { id: Some("GHSA-0000-0000-0000".to_string()), package: Some(package.to_string()), ..Advisory::default() });
+        db.insert(package.to_string(), shared);
+    }
+    assert_eq!(db.find_all("ghsa-0000-0000-0000").len(), 2);
+    assert!(db.find_all("GHSA-1111-1111-1111").is_empty());
+
+    let lockfile: Lockfile = toml::from_str("[[package]]\nname = \"example\"\nversion = \"1.2.3\"\n").unwrap();
+    let locked = locked_dependencies(&lockfile);
+    assert_eq!(locked[0].version.semver, semver::Version::parse("1.2.3").ok());
+}
+
+#[test]
+fn test_advisory_storage() {
+    let advisory = |id: &str, date: &str| ParentalAdvisory {
+        body: None,
+        source: None,
+        advisory: Some(Advisory {
+            id: Some(id.to_string()),
+            package: Some("example".to_string()),
+            date: Some(date.to_string()),
+            url: None,
+            categories: None,
+            keywords: None,
+            aliases: None,
+            cvss: None,
+            informational: None,
+            withdrawn: None,
+        }),
+        versions: None,
+        affected: None,
+    };
+
+    let mut db = SecurityDatabase::new();
+    db.insert("example".to_string(), advisory("RUSTSEC-2021-0002", "2021-02-01"));
+    db.insert("example".to_string(), advisory("RUSTSEC-2020-0001", "2020-01-01"));
+    db.insert("example".to_string(), advisory("RUSTSEC-2021-0002", "2021-02-01"));
+    db.sort();
+
+    let stored = db.advisories.get("example").unwrap();
+    assert_eq!(stored.len(), 2);
+    assert_eq!(stored[0].advisory.clone().unwrap().id.unwrap(), "RUSTSEC-2020-0001");
+}
+
+#[test]
+fn test_target_triples() {
+    let mut db = SecurityDatabase::new();
+
+    db.set_target("x86_64-pc-windows-msvc");
+    assert_eq!((db.target_arch.as_str(), db.target_os.as_str()), ("x86_64", "windows"));
+
+    db.set_target("i686-unknown-linux-gnu");
+    assert_eq!((db.target_arch.as_str(), db.target_os.as_str()), ("x86", "linux"));
+
+    db.set_target("aarch64-apple-darwin");
+    assert_eq!((db.target_arch.as_str(), db.target_os.as_str()), ("aarch64", "macos"));
+
+    db.set_target("thumbv7em-none-eabihf");
+    assert_eq!((db.target_arch.as_str(), db.target_os.as_str()), ("arm", "none"));
+}
+
+#[test]
+fn test_archive_verification() {
+    let mut db = SecurityDatabase::new();
+    let digest = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    db.pinned_sha256 = Some(digest.to_uppercase());
+    assert_eq!(db.verify(b"hello").unwrap(), digest);
+
+    db.pinned_sha256 = Some("0".repeat(64));
+    assert!(matches!(db.verify(b"hello").unwrap_err().inner, Errors::DBIntegrityMismatch));
+
+    // Snapshots are only used for the archive they were made from
+    let cached = DatabaseCache {
+        schema: SNAPSHOT_SCHEMA,
+        fetched: 0,
+        etag: None,
+        url: db.url.clone(),
+        sha256: Some(digest.to_string()),
+        advisories: HashMap::new(),
+    };
+    assert!(!db.is_cache_for(&cached));
+
+    db.pinned_sha256 = Some(digest.to_string());
+    assert!(db.is_cache_for(&cached));
+
+    db.url = "https://mirror.example.com/advisory-db.zip".to_string();
+    assert!(!db.is_cache_for(&cached));
+
+    // A zip without any advisories in it is rejected rather than read as an empty database
+    let path = std::env::temp_dir().join(format!("version-checker-empty-{}.zip", std::process::id()));
+    let mut writer = ZipWriter::new(File::create(&path).unwrap());
+    writer.start_file("README.md", FileOptions::default()).unwrap();
+    writer.write_all(b"# Not an advisory database").unwrap();
+    writer.finish().unwrap();
+
+    let result = SecurityDatabase::new().load_local(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(result.unwrap_err().inner, Errors::DBUnreadable));
+}
+
+#[test]
+fn test_database_snapshot() {
+    let root = std::env::temp_dir().join(format!("version-checker-snapshot-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let advisory = ParentalAdvisory {
+        body: Some("# Example".to_string()),
+        source: Some("rustsec".to_string()),
+        advisory: Some(Advisory { id: Some("RUSTSEC-2021-0001".to_string()), package: Some("example".to_string()), ..Advisory::default() }),
+        versions: None,
+        affected: None,
+    };
+
+    let mut db = SecurityDatabase::new();
+    db.cache_dir = Some(root.clone());
+    db.fetched = 1_600_000_000;
+    db.etag = Some("\"abc\"".to_string());
+    db.insert("example".to_string(), advisory);
+    db.write_cache();
+
+    let mut reader = SecurityDatabase::new();
+    reader.cache_dir = Some(root.clone());
+    let cached = reader.read_cache().unwrap();
+    assert_eq!(cached.schema, SNAPSHOT_SCHEMA);
+    assert_eq!(cached.fetched, 1_600_000_000);
+    assert_eq!(cached.etag, Some("\"abc\"".to_string()));
+    assert_eq!(cached.advisories.get("example").unwrap()[0].body, Some("# Example".to_string()));
+
+    // A snapshot from another version of the layout is ignored rather than misread
+    let mut changed = cached.clone();
+    changed.schema = SNAPSHOT_SCHEMA + 1;
+    std::fs::write(root.join("security.bin"), bincode::serialize(&changed).unwrap()).unwrap();
+    assert!(reader.read_cache().is_none());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_ignore_dates() {
+    let config: Config = toml::from_str("[[ignore]]\nid = \"RUSTSEC-2020-0001\"\nreason = \"a\"\nuntil = 2021-12-31\n\n[[ignore]]\nid = \"RUSTSEC-2020-0002\"\nreason = \"b\"\nuntil = \"2021-12-31\"\n").unwrap();
+    let ignored = config.ignore.unwrap();
+    assert_eq!(ignored[0].until, Some("2021-12-31".to_string()));
+    assert_eq!(ignored[1].until, Some("2021-12-31".to_string()));
+
+    assert!(toml::from_str::<Config>("[[ignore]]\nid = \"RUSTSEC-2020-0001\"\nreason = \"a\"\nuntil = \"2021-02-30\"\n").is_err());
+    assert!(toml::from_str::<Config>("[[ignore]]\nid = \"RUSTSEC-2020-0001\"\nreason = \"a\"\nuntil = \"next year\"\n").is_err());
+
+    // The parser's reason, such as the invalid date, is kept in the error shown to the user
+    let error = toml::from_str::<Config>("[[ignore]]\nid = \"RUSTSEC-2020-0001\"\nreason = \"a\"\nuntil = \"next year\"\n").unwrap_err();
+    let shown = VerificationError::new(Errors::ConfigInvalid(error.to_string())).to_string();
+    assert!(shown.contains("`until` has an invalid date next year"));
+
+    // Entries stop matching once their date has passed
+    let advisory = ParentalAdvisory {
+        body: None,
+        source: None,
+        advisory: Some(Advisory { id: Some("RUSTSEC-2020-0001".to_string()), ..Advisory::default() }),
+        versions: None,
+        affected: None,
+    };
+    let entry = |until: Option<&str>| IgnoredAdvisory { id: "RUSTSEC-2020-0001".to_string(), reason: "accepted".to_string(), until: until.map(|until| until.to_string()) };
+    let mut db = SecurityDatabase::new();
+
+    db.ignored = vec![entry(Some("2000-01-01"))];
+    assert!(db.ignore_entry(&advisory).is_none());
+    db.ignored = vec![entry(Some("2999-01-01"))];
+    assert!(db.ignore_entry(&advisory).is_some());
+    db.ignored = vec![entry(None)];
+    assert!(db.ignore_entry(&advisory).is_some());
+}
+
+#[test]
+fn test_function_references() {
+    let sources = SourceIndex {
+        files: vec![(
+            "src/main.rs".to_string(),
+            "use smallvec::SmallVec;\n\nfn main() {\n    // v.insert_many(0, items);\n    v.insert_many(0, items);\n}".to_string(),
+        )],
+    };
+
+    assert_eq!(sources.find_reference("smallvec::SmallVec::insert_many"), Some(("src/main.rs".to_string(), 5)));
+    assert_eq!(sources.find_reference("smallvec::SmallVec::retain"), None);
+
+    let index = |content: &str| SourceIndex { files: vec![("src/lib.rs".to_string(), content.to_string())] };
+
+    // Mentioning the crate's name isn't enough, nor is a method of the same name on a type from elsewhere
+    assert_eq!(index("// smallvec is not used
+fn f(v: Vec<u8>) { v.insert_many(0, items); }").find_reference("smallvec::SmallVec::insert_many"), None);
+    assert_eq!(index("use smallvec::smallvec;
+fn f(v: Other) { v.insert_many(0, items); }").find_reference("smallvec::SmallVec::insert_many"), None);
+    assert_eq!(index("fn f() { let v = smallvec::SmallVec::<[u8; 4]>::new(); SmallVec::insert_many(&mut v, 0, items); }").find_reference("smallvec::SmallVec::insert_many"), Some(("src/lib.rs".to_string(), 1)));
+
+    // Functions count when called by their full path or after being imported
+    assert_eq!(index("fn f() { base64::decode(input); }").find_reference("base64::decode"), Some(("src/lib.rs".to_string(), 1)));
+    assert_eq!(index("use base64::decode;
+
+fn f() { decode(input); }").find_reference("base64::decode"), Some(("src/lib.rs".to_string(), 3)));
+    assert_eq!(index("use base64::encode;
+
+fn f() { decode(input); }").find_reference("base64::decode"), None);
+}
+
+#[test]
+fn test_cvss_scores() {
+    let critical = Cvss::parse("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H").unwrap();
+    assert_eq!(critical.score, 9.8);
+    assert_eq!(critical.severity, Severity::Critical);
+
+    let medium = Cvss::parse("CVSS:3.0/AV:L/AC:L/PR:L/UI:N/S:U/C:N/I:N/A:H").unwrap();
+    assert_eq!(medium.score, 5.5);
+    assert_eq!(medium.severity, Severity::Medium);
+
+    assert!(Cvss::parse("CVSS:2.0/AV:N/AC:L/Au:N/C:P/I:P/A:P").is_none());
+}
+
+#[test]
+fn test_osv_ranges() {
+    let event = |kind: &str, version: &str| {
+        let mut event = HashMap::new();
+        event.insert(kind.to_string(), version.to_string());
+        event
+    };
+    let ranges = vec![OsvRange {
+        kind: "SEMVER".to_string(),
+        events: vec![event("introduced", "1.0.0"), event("fixed", "1.2.4"), event("introduced", "1.3.0"), event("fixed", "1.3.2")],
+    }];
+
+    let versions = convert_ranges(&ranges);
+    assert_eq!(versions.unaffected.unwrap(), vec!["< 1.0.0".to_string()]);
+    assert_eq!(versions.patched.unwrap(), vec![">= 1.2.4, < 1.3.0".to_string(), ">= 1.3.2".to_string()]);
+
+    let last_affected = vec![OsvRange {
+        kind: "SEMVER".to_string(),
+        events: vec![event("introduced", "0"), event("last_affected", "1.2.0")],
+    }];
+    assert_eq!(convert_ranges(&last_affected).patched.unwrap(), vec!["> 1.2.0".to_string()]);
+
+    let overlapping = vec![
+        OsvRange { kind: "SEMVER".to_string(), events: vec![event("introduced", "0"), event("fixed", "1.0.0")] },
+        OsvRange { kind: "SEMVER".to_string(), events: vec![event("introduced", "0.5.0"), event("fixed", "2.0.0")] },
+    ];
+    let versions = convert_ranges(&overlapping);
+    assert!(versions.unaffected.unwrap().is_empty());
+    assert_eq!(versions.patched.unwrap(), vec![">= 2.0.0".to_string()]);
+
+    // A document which isn't valid OSV stops the load instead of being skipped
+    let root = std::env::temp_dir().join(format!("version-checker-osv-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("GHSA-0000-0000-0000.json"), "{\"id\": \"GHSA-0000-0000-0000\", \"affected\": [").unwrap();
+
+    let result = SecurityDatabase::new().read_osv_directory(root.as_path());
+    std::fs::remove_dir_all(&root).unwrap();
+    assert!(matches!(result.unwrap_err().inner, Errors::OsvDocumentInvalid(reason) if reason.contains("GHSA-0000-0000-0000.json")));
+}
+
+#[test]
+fn test_frontmatter_parser() {
+    let text = "```toml\n[advisory]\nid = \"RUSTSEC-2021-0001\"\npackage = \"example\"\n\n[versions]\npatched = [\">= 1.0.1\"]\n```\n\n# Example advisory\n\nUses ``` inline.";
+    let advisory = parse_advisory(text).unwrap();
+
+    assert_eq!(advisory.advisory.unwrap().package.unwrap(), "example");
+    assert_eq!(advisory.body.unwrap(), "\n# Example advisory\n\nUses ``` inline.");
+    assert!(parse_advisory("# No frontmatter").is_none());
+}
+
+#[test]
+fn test_toolchain_advisories() {
+    let root = std::env::temp_dir().join(format!("version-checker-toolchain-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let advisory = |package: &str, patched: &str| format!("```toml\n[advisory]\nid = \"CVE-2021-0001\"\npackage = \"{}\"\ndate = \"2021-01-01\"\n\n[versions]\npatched = [\"{}\"]\n```\n\n# Example", package, patched);
+
+    std::fs::create_dir_all(root.join("crates/cargo")).unwrap();
+    std::fs::create_dir_all(root.join("rust/cargo")).unwrap();
+    std::fs::write(root.join("crates/cargo/RUSTSEC-2021-0001.md"), advisory("cargo", ">= 0.50.0").replace("CVE-2021-0001", "RUSTSEC-2021-0001")).unwrap();
+    std::fs::write(root.join("rust/cargo/CVE-2021-0001.md"), advisory("cargo", ">= 1.50.0")).unwrap();
+
+    let mut db = SecurityDatabase::new();
+    db.load_local(root.to_str().unwrap()).unwrap();
+
+    assert_eq!(db.advisories.get("cargo").unwrap().len(), 1);
+    assert_eq!(db.toolchain_advisories()[0].0, "cargo");
+    assert_eq!(toolchain::affecting_advisories(&db, &semver::Version::parse("1.49.0").unwrap()).len(), 1);
+    assert!(toolchain::affecting_advisories(&db, &semver::Version::parse("1.52.1").unwrap()).is_empty());
+
+    // Informational advisories are listed without counting as vulnerabilities, those below the minimum severity are left out
+    std::fs::create_dir_all(root.join("rust/std")).unwrap();
+    std::fs::write(root.join("rust/std/CVE-2021-0002.md"), advisory("std", ">= 1.50.0").replace("CVE-2021-0001", "CVE-2021-0002").replace("[versions]", "informational = \"unsound\"\n\n[versions]")).unwrap();
+    std::fs::write(root.join("rust/std/CVE-2021-0003.md"), advisory("std", ">= 1.50.0").replace("CVE-2021-0001", "CVE-2021-0003").replace("[versions]", "cvss = \"CVSS:3.0/AV:L/AC:L/PR:L/UI:N/S:U/C:N/I:N/A:H\"\n\n[versions]")).unwrap();
+
+    let mut db = SecurityDatabase::new();
+    db.min_severity = Severity::High;
+    db.load_local(root.to_str().unwrap()).unwrap();
+
+    let affecting = toolchain::affecting_advisories(&db, &semver::Version::parse("1.49.0").unwrap());
+    assert_eq!(affecting.len(), 2);
+    assert_eq!(affecting.iter().filter(|(_, advisory)| db.is_vulnerability(advisory)).count(), 1);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_locked_versions() {
+    let lockfile: Lockfile = toml::from_str("[[package]]\nname = \"serde\"\nversion = \"1.0.130\"\n\n[[package]]\nname = \"rand\"\nversion = \"0.7.3\"\n\n[[package]]\nname = \"rand\"\nversion = \"0.8.4\"\n").unwrap();
+
+    assert_eq!(lockfile::resolve(&lockfile, "serde", "1"), semver::Version::parse("1.0.130").ok());
+    assert_eq!(lockfile::resolve(&lockfile, "rand", "0.7"), semver::Version::parse("0.7.3").ok());
+    assert_eq!(lockfile::resolve(&lockfile, "rand", "*"), semver::Version::parse("0.8.4").ok());
+    assert_eq!(lockfile::resolve(&lockfile, "rand", ""), None);
+    assert_eq!(lockfile::resolve(&lockfile, "serde", ""), semver::Version::parse("1.0.130").ok());
+
+    // Packages from the lockfile graph are looked up by their exact version, a caret requirement could pick a newer one
+    let duplicated: Lockfile = toml::from_str("[[package]]\nname = \"rand\"\nversion = \"0.8.3\"\n\n[[package]]\nname = \"rand\"\nversion = \"0.8.4\"\n").unwrap();
+    assert_eq!(lockfile::resolve(&duplicated, "rand", "0.8.3"), semver::Version::parse("0.8.4").ok());
+    assert_eq!(lockfile::resolve(&duplicated, "rand", "=0.8.3"), semver::Version::parse("0.8.3").ok());
+}
+
+#[test]
+fn test_lockfile_entries() {
+    let lockfile: Lockfile = toml::from_str("[[package]]\nname = \"rand\"\nversion = \"0.7.3\"\n\n[[package]]\nname = \"rand\"\nversion = \"0.8.4\"\ndependencies = [\"rand_core\"]\n\n[[package]]\nname = \"rand_core\"\nversion = \"0.6.3\"\n").unwrap();
+
+    assert_eq!(lockfile::find_package(&lockfile, "rand_core").unwrap().version, "0.6.3");
+    assert_eq!(lockfile::find_package(&lockfile, "rand 0.8.4 (registry+https://github.com/rust-lang/crates.io-index)").unwrap().dependencies, Some(vec!["rand_core".to_string()]));
+    assert!(lockfile::find_package(&lockfile, "rand 0.6.5").is_none());
+
+    // Direct dependencies follow the edges of the package being checked rather than the highest matching version
+    let rooted: Lockfile = toml::from_str("[[package]]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = [\"rand 0.7.3\"]\n\n[[package]]\nname = \"rand\"\nversion = \"0.7.3\"\n\n[[package]]\nname = \"rand\"\nversion = \"0.8.4\"\n").unwrap();
+    assert_eq!(lockfile::resolve_direct(&rooted, Some("app"), "rand", "*"), semver::Version::parse("0.7.3").ok());
+    assert_eq!(lockfile::resolve_direct(&rooted, Some("app"), "rand", ""), semver::Version::parse("0.7.3").ok());
+    assert_eq!(lockfile::resolve_direct(&rooted, Some("app"), "rand", "=0.8.4"), semver::Version::parse("0.8.4").ok());
+    assert_eq!(lockfile::resolve_direct(&rooted, None, "rand", "*"), semver::Version::parse("0.8.4").ok());
+}
+
+#[test]
+fn test_lockfile_only() {
+    let lockfile: Lockfile = toml::from_str("[[package]]\nname = \"app\"\nversion = \"0.1.0\"\n\n[[package]]\nname = \"vendored\"\nversion = \"1.0.0\"\nsource = \"git+https://example.com/vendored#abc\"\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.130\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n[[package]]\nname = \"sparse\"\nversion = \"0.1.0\"\nsource = \"sparse+https://index.crates.io/\"\n").unwrap();
+    let packages = lockfile.package.clone().unwrap();
+
+    assert_eq!(packages.iter().map(lockfile::is_crates_io).collect::<Vec<bool>>(), vec![false, false, true, true]);
+
+    // Path and git packages are checked against the database without being looked up on crates.io
+    let mut crate_mgr = CratesIOManager::new();
+    crate_mgr.lockfile = Some(Lockfile { version: None, package: Some(packages[..2].to_vec()) });
+
+    let mut db = SecurityDatabase::new();
+    db.insert("vendored".to_string(), ParentalAdvisory {
+        body: None,
+        source: None,
+        advisory: Some(Advisory { id: Some("RUSTSEC-2021-0001".to_string()), package: Some("vendored".to_string()), ..Advisory::default() }),
+        versions: Some(AdvisoryVersions { patched: Some(vec![">= 1.0.1".to_string()]), unaffected: None }),
+        affected: None,
+    });
+
+    assert_eq!(crate_mgr.check_lockfile(&OutputManager::new(0, 112), &db).unwrap(), (0, 0, 1, 2, 0));
+}
+
+#[test]
+fn test_workspace_members() {
+    let root = std::env::temp_dir().join(format!("version-checker-workspace-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let manifest = root.join("Cargo.toml");
+
+    for member in &["crates/core", "crates/cli", "crates/scratch"] {
+        std::fs::create_dir_all(root.join(member)).unwrap();
+        std::fs::write(root.join(member).join("Cargo.toml"), "[package]\nname = \"member\"\n").unwrap();
+    }
+    std::fs::write(&manifest, "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/scratch\"]\n\n[workspace.dependencies]\nserde = \"1.0\"\n").unwrap();
+
+    let raw = workspace::read_manifest(manifest.as_path()).unwrap();
+    let members = workspace::members(manifest.as_path(), &raw).unwrap();
+    assert_eq!(members, vec![root.join("crates/cli/Cargo.toml"), root.join("crates/core/Cargo.toml")]);
+
+    let shared = workspace::shared_dependencies(&raw);
+    let member: toml::Value = toml::from_str("workspace = true\noptional = true").unwrap();
+    let inherited = workspace::inherit("serde", &member, &shared);
+    assert_eq!(inherited.get("version").and_then(|version| version.as_str()), Some("1.0"));
+    assert_eq!(inherited.get("optional").and_then(|optional| optional.as_bool()), Some(true));
+    assert!(inherited.get("workspace").is_none());
+
+    // Members share the Cargo.lock beside the workspace root
+    let found = workspace::root_manifest(root.join("crates/cli/Cargo.toml").as_path()).unwrap();
+    assert_eq!(found, manifest.canonicalize().unwrap());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_target_cfg() {
+    let linux = Target::from_triple("x86_64-unknown-linux-gnu");
+    let windows = Target::from_triple("i686-pc-windows-msvc");
+
+    assert!(linux.matches("cfg(unix)"));
+    assert!(!windows.matches("cfg(unix)"));
+    assert!(windows.matches("cfg(windows)"));
+    assert!(windows.matches(r#"cfg(all(target_env = "msvc", target_pointer_width = "32"))"#));
+    assert!(linux.matches(r#"cfg(not(target_os = "macos"))"#));
+    assert!(linux.matches(r#"cfg(any(target_os = "macos", target_family = "unix"))"#));
+    assert!(linux.matches("x86_64-unknown-linux-gnu"));
+    assert!(!linux.matches("cfg(all(unix"));
+
+    assert!(linux.matches(r#"cfg(target_endian = "little")"#));
+    assert!(Target::from_triple("s390x-unknown-linux-gnu").matches(r#"cfg(target_endian = "big")"#));
+    assert!(Target::from_triple("powerpc64-unknown-linux-gnu").matches(r#"cfg(all(target_arch = "powerpc64", target_endian = "big"))"#));
+    assert!(Target::from_triple("powerpc64le-unknown-linux-gnu").matches(r#"cfg(all(target_arch = "powerpc64", target_endian = "little"))"#));
+    assert!(Target::from_triple("mipsel-unknown-linux-gnu").matches(r#"cfg(all(target_arch = "mips", target_endian = "little"))"#));
+    assert!(Target::from_triple("mips-unknown-linux-gnu").matches(r#"cfg(target_endian = "big")"#));
+    assert!(Target::from_triple("thumbv7em-none-eabihf").matches(r#"cfg(all(target_arch = "arm", target_os = "none"))"#));
+    assert!(Target::from_triple("sparcv9-sun-solaris").matches(r#"cfg(all(target_arch = "sparc64", target_pointer_width = "64"))"#));
+
+    let wasm = Target::from_triple("wasm32-unknown-unknown");
+    assert_eq!(wasm.os, "unknown");
+    assert!(wasm.matches(r#"cfg(target_family = "wasm")"#));
+}
+
+#[test]
+fn test_manifest_kinds() {
+    let raw: toml::Value = toml::from_str(include_str!("../test-manifest.toml")).unwrap();
+    let entries = manifest_entries(&raw, &HashMap::new()).unwrap();
+    let find = |name: &str| entries.iter().find(|entry| entry.name == name).unwrap();
+
+    assert_eq!(find("cargo-test-macro").kind, DependencyKind::Dev);
+    assert_eq!(find("tar").kind, DependencyKind::Normal);
+    assert_eq!(entries.iter().filter(|entry| entry.name == "tar").count(), 2);
+    assert_eq!(find("miow").target, Some("cfg(windows)".to_string()));
+    assert_eq!(find("winapi").target, Some("cfg(windows)".to_string()));
+}
+
+#[test]
+fn test_markdown_details() {
+    let body = "\n# Example advisory\n\n## Impact\nCalling `insert_many` can\nwrite out of bounds, see [the issue](https://example.com).\n- first\n- second\n\nSecond paragraph.";
+    let paragraph = markdown::first_paragraph(body);
+
+    assert_eq!(paragraph.len(), 5);
+    assert_eq!(markdown::render(&paragraph, 80), vec![
+        "\x1b[1mImpact\x1b[0m".to_string(),
+        "Calling \x1b[36minsert_many\x1b[0m can write out of bounds, see the issue.".to_string(),
+        "• first".to_string(),
+        "• second".to_string(),
+    ]);
+    assert_eq!(markdown::render(&["Fixed (in 1.2) by [a patch](https://example.com), see [x] above.".to_string()], 80), vec![
+        "Fixed (in 1.2) by a patch, see [x] above.".to_string(),
+    ]);
+    assert_eq!(markdown::render(&["one two three".to_string()], 7), vec!["one two".to_string(), "three".to_string()]);
+    assert_eq!(markdown::visible_width("\x1b[36mcode\x1b[0m"), 4);
+}
+
+/// Compares loading an advisory-db zip before and after it was parsed in memory: the old path wrote the archive to disk,
+/// reopened it and parsed each entry in turn with regexes, the new one parses the in-memory archive in parallel.
+/// Uses the zip at `VERSION_CHECKER_BENCH_DB` when set and a generated one otherwise, run with
+/// `cargo test --release bench_database_load -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_database_load() {
+    let archive = match std::env::var("VERSION_CHECKER_BENCH_DB") {
+        Ok(path) => std::fs::read(path).expect("VERSION_CHECKER_BENCH_DB should point at an advisory-db zip"),
+        Err(_) => bench_archive(500),
+    };
+    let runs = 10;
+
+    let started = Instant::now();
+    for _ in 0..runs {
+        let db = bench_disk_load(archive.as_slice());
+        assert!(!db.advisories.is_empty());
+    }
+    let disk = started.elapsed() / runs;
+
+    let started = Instant::now();
+    for _ in 0..runs {
+        let mut db = SecurityDatabase::new();
+        db.read_archive(archive.as_slice()).unwrap();
+        assert!(!db.advisories.is_empty());
+    }
+    let memory = started.elapsed() / runs;
+
+    println!("Disk and regex: {:?}, in memory and parallel: {:?} on average", disk, memory);
+}
+
+/// The loading path from before the archive was parsed in memory, kept here only to compare against
+fn bench_disk_load(archive: &[u8]) -> SecurityDatabase {
+    let mut db = SecurityDatabase::new();
+    let path = std::env::temp_dir().join(format!("version-checker-bench-{}.zip", std::process::id()));
+    std::fs::write(&path, archive).unwrap();
+
+    let mut zipped = ZipArchive::new(File::open(&path).unwrap()).unwrap();
+    let paths: Vec<String> = zipped.file_names().map(|name| name.to_string()).collect();
+
+    for path in paths {
+        if path.ends_with(".md") && path.contains("/crates/") {
+            let mut text = String::new();
+            zipped.by_name(path.as_str()).unwrap().read_to_string(&mut text).unwrap();
+
+            let frontmatter_matcher = Regex::new(r#"`{3}toml(.*[\n])*`{3}"#).unwrap();
+            let frontmatter_replacer = Regex::new(r#"`{3}\n|`{3}toml\n"#).unwrap();
+
+            if let Some(captured) = frontmatter_matcher.captures(text.as_str()) {
+                let frontmatter = frontmatter_replacer.replace_all(captured.get(0).unwrap().as_str(), "").to_string().replace("```", "");
+                let body = frontmatter_matcher.replace(text.as_str(), "").to_string();
+
+                if let Ok(mut advisory) = toml::from_str::<ParentalAdvisory>(frontmatter.as_str()) {
+                    advisory.body = Some(body);
+                    if let Some(crate_name) = advisory.advisory.clone().and_then(|details| details.package) {
+                        db.insert(crate_name, advisory);
+                    }
+                }
+            }
+        }
+    }
+    db.sort();
+
+    std::fs::remove_file(&path).unwrap();
+    db
+}
+
+/// A zip laid out like the advisory-db with the given number of advisories, spread over a fifth as many crates
+fn bench_archive(count: usize) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(vec![]));
+
+    for index in 0..count {
+        let name = format!("crate-{}", index % (count / 5).max(1));
+        let text = format!(
+            "```toml\n[advisory]\nid = \"RUSTSEC-2020-{0:04}\"\npackage = \"{1}\"\ndate = \"2020-01-{2:02}\"\n\
+             url = \"https://example.com/{1}/issues/{0}\"\ncategories = [\"memory-corruption\"]\nkeywords = [\"overflow\"]\n\
+             aliases = [\"CVE-2020-{0:05}\"]\n\n[versions]\npatched = [\">= 1.{0}.0\"]\nunaffected = [\"< 0.1.0\"]\n```\n\n\
+             # Buffer overflow in {1}\n\n{3}\n",
+            index, name, index % 28 + 1, "Affected versions of this crate did not check the length of the input. ".repeat(12),
+        );
+
+        writer.start_file(format!("advisory-db-master/crates/{}/RUSTSEC-2020-{:04}.md", name, index), FileOptions::default()).unwrap();
+        writer.write_all(text.as_bytes()).unwrap();
+    }
+
+    writer.finish().unwrap().into_inner()
+}
@@ -1,6 +1,7 @@
 use std::fmt;
 use serde::__private::Formatter;
 
+#[derive(Debug)]
 pub enum Errors {
     /// # Errors for the Security Database
     // Unable to update advisory database
@@ -12,9 +13,26 @@ pub enum Errors {
     // Unable to write advisory database
     DBNotWriteable,
 
+    // No cached advisory database available while offline
+    DBCacheUnavailable,
+
+    // Downloaded advisory database doesn't match its expected checksum
+    DBIntegrityMismatch,
+
+    // An OSV document couldn't be read or parsed, with its path and the reason
+    OsvDocumentInvalid(String),
+
     /// # Errors for the Cargo.toml file
     // Unable to locate Cargo.toml
     CrateFileNotFound,
+
+    /// # Errors for the Cargo.lock file
+    // Unable to read or parse Cargo.lock
+    LockfileInvalid,
+
+    /// # Errors for the version-checker.toml file
+    // Unable to read or parse version-checker.toml, with the reason given by the parser
+    ConfigInvalid(String),
 }
 
 pub enum DisplayMode {
@@ -47,13 +65,23 @@ impl VerificationError {
     }
 }
 
-pub fn log(_e: &VerificationError, f: &mut fmt::Formatter, display_mode: DisplayMode) -> fmt::Result {
+pub fn log(e: &VerificationError, f: &mut fmt::Formatter, display_mode: DisplayMode) -> fmt::Result {
     return match display_mode {
         DisplayMode::Debug => {
-            write!(f, "This is a debug displayed error")
+            write!(f, "{:?}", e.inner)
         }
         DisplayMode::User => {
-            write!(f, "This is a user displayed error")
+            match &e.inner {
+                Errors::DBUpdateFailed => write!(f, "Unable to download the advisory database"),
+                Errors::DBUnreadable => write!(f, "Unable to read the advisory database"),
+                Errors::DBNotWriteable => write!(f, "Unable to write the advisory database to the cache directory"),
+                Errors::DBCacheUnavailable => write!(f, "No cached copy of the advisory database is available while offline"),
+                Errors::DBIntegrityMismatch => write!(f, "The downloaded advisory database doesn't match its expected SHA-256"),
+                Errors::OsvDocumentInvalid(reason) => write!(f, "Unable to read the OSV advisory {}", reason),
+                Errors::CrateFileNotFound => write!(f, "Unable to find or read Cargo.toml"),
+                Errors::LockfileInvalid => write!(f, "Unable to find or read Cargo.lock"),
+                Errors::ConfigInvalid(reason) => write!(f, "Unable to read version-checker.toml, {}", reason),
+            }
         }
     };
 }
\ No newline at end of file
@@ -1 +1,4 @@
-pub mod security;
\ No newline at end of file
+pub mod security;
+pub mod config;
+pub mod osv;
+pub mod lockfile;
\ No newline at end of file
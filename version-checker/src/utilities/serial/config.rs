@@ -0,0 +1,77 @@
+use serde::Deserializer;
+use serde::de::Error;
+use serde_derive::Deserialize;
+
+// Settings read from version-checker.toml, found beside the manifest
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Config {
+    pub ignore: Option<Vec<IgnoredAdvisory>>,
+    pub sources: Option<Vec<AdvisorySource>>,
+}
+
+// An advisory which has been assessed and accepted, by RUSTSEC id or by one of its aliases
+#[derive(Debug, Clone, Deserialize)]
+pub struct IgnoredAdvisory {
+    pub id: String,
+    pub reason: String,
+    #[serde(default, deserialize_with = "deserialize_date")]
+    pub until: Option<String>,
+}
+
+// Somewhere to read advisories from, sources without a path download the RustSec database.
+// A path can be an advisory-db style directory or zip, or a directory of OSV documents when the format is "osv"
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdvisorySource {
+    pub name: String,
+    pub path: Option<String>,
+    pub format: Option<String>,
+}
+
+impl AdvisorySource {
+    pub fn rustsec() -> AdvisorySource {
+        AdvisorySource {
+            name: "rustsec".to_string(),
+            path: None,
+            format: None,
+        }
+    }
+}
+
+// Reads `until` from either a TOML date such as 2021-12-31 or a string, keeping it as `YYYY-MM-DD` so dates compare as text
+fn deserialize_date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let text = match <toml::Value as serde::Deserialize>::deserialize(deserializer)? {
+        toml::Value::Datetime(datetime) => datetime.to_string(),
+        toml::Value::String(text) => text,
+        _ => return Err(D::Error::custom("`until` must be a date written as YYYY-MM-DD")),
+    };
+    let date = text.split(|character| character == 'T' || character == ' ').next().unwrap_or_default();
+
+    if !is_valid_date(date) {
+        return Err(D::Error::custom(format!("`until` has an invalid date {}, expected YYYY-MM-DD", text)));
+    }
+
+    Ok(Some(date.to_string()))
+}
+
+fn is_valid_date(date: &str) -> bool {
+    let pieces = date.split('-').collect::<Vec<&str>>();
+
+    if pieces.len() != 3 || pieces[0].len() != 4 || pieces[1].len() != 2 || pieces[2].len() != 2 {
+        return false;
+    }
+
+    let (year, month, day) = match (pieces[0].parse::<u32>(), pieces[1].parse::<u32>(), pieces[2].parse::<u32>()) {
+        (Ok(year), Ok(month), Ok(day)) => (year, month, day),
+        _ => return false,
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+
+    day >= 1 && day <= days
+}
@@ -0,0 +1,18 @@
+use serde_derive::Deserialize;
+
+// Cargo.lock, the versions cargo resolved for every package in the dependency graph
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Lockfile {
+    pub version: Option<u32>,
+    pub package: Option<Vec<LockedPackage>>,
+}
+
+// Dependencies are listed as "name", or "name version" when several versions of the package are locked
+#[derive(Debug, Clone, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    pub source: Option<String>,
+    pub checksum: Option<String>,
+    pub dependencies: Option<Vec<String>>,
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use serde_derive::Deserialize;
+
+// JSON advisory in the Open Source Vulnerability format, see https://ossf.github.io/osv-schema/
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvAdvisory {
+    pub id: String,
+    pub published: Option<String>,
+    pub withdrawn: Option<String>,
+    pub aliases: Option<Vec<String>>,
+    pub summary: Option<String>,
+    pub details: Option<String>,
+    pub severity: Option<Vec<OsvSeverity>>,
+    pub affected: Option<Vec<OsvAffected>>,
+    pub references: Option<Vec<OsvReference>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvSeverity {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub score: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvAffected {
+    pub package: Option<OsvPackage>,
+    pub ranges: Option<Vec<OsvRange>>,
+    pub ecosystem_specific: Option<OsvEcosystemSpecific>,
+    pub database_specific: Option<OsvDatabaseSpecific>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvPackage {
+    pub ecosystem: String,
+    pub name: String,
+}
+
+// Each event holds a single key, one of introduced, fixed, last_affected or limit
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvRange {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub events: Vec<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvEcosystemSpecific {
+    pub affects: Option<OsvAffects>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvAffects {
+    pub arch: Option<Vec<String>>,
+    pub os: Option<Vec<String>>,
+    pub functions: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvDatabaseSpecific {
+    pub categories: Option<Vec<String>>,
+    pub informational: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsvReference {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub url: String,
+}
\ No newline at end of file
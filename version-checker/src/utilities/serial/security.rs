@@ -1,15 +1,18 @@
-use serde_derive::Deserialize;
+use std::collections::HashMap;
+
+use serde_derive::{Deserialize, Serialize};
 
 // TOML security advisory, found in the MD file
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParentalAdvisory {
     pub body: Option<String>,
+    pub source: Option<String>,
     pub advisory: Option<Advisory>,
     pub versions: Option<Version>,
     pub affected: Option<Affected>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Advisory {
 
     pub id: Option<String>,
@@ -20,17 +23,35 @@ pub struct Advisory {
     pub keywords: Option<Vec<String>>,
     pub aliases: Option<Vec<String>>,
     pub cvss: Option<String>,
+    pub informational: Option<String>,
+    pub withdrawn: Option<String>,
 
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Version {
     pub patched: Option<Vec<String>>,
     pub unaffected: Option<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Affected {
     pub arch: Option<Vec<String>>,
     pub os: Option<Vec<String>>,
+    pub functions: Option<HashMap<String, Vec<String>>>,
+}
+
+// Bumped whenever the layout of DatabaseCache or the types within it change, so older snapshots are rebuilt
+pub const SNAPSHOT_SCHEMA: u32 = 3;
+
+// Binary snapshot of the parsed advisory database, stored on disk between runs.
+// The schema must stay the first field, it is read on its own before the rest of the snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseCache {
+    pub schema: u32,
+    pub fetched: u64,
+    pub etag: Option<String>,
+    pub url: String,
+    pub sha256: Option<String>,
+    pub advisories: HashMap<String, Vec<ParentalAdvisory>>,
 }
\ No newline at end of file
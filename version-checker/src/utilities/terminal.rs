@@ -1,2 +1,3 @@
 pub mod input;
-pub mod output;
\ No newline at end of file
+pub mod output;
+pub mod markdown;
\ No newline at end of file
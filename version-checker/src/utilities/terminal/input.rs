@@ -1,35 +1,304 @@
+use std::process::exit;
+use std::path::Path;
+use std::fs::read_to_string;
 use std::io::Broad
 
-use crate::utilities::terminal::output::{OutputManager, DisplayLine};
+use crate::utilities::terminal::output::{OutputManager, DisplayLine, advisory_title};
+use crate::utilities::errors::{Errors, VerificationError};
+use crate::utilities::serial::config::{Config, AdvisorySource};
+use crate::utilities::serial::lockfile::Lockfile;
 use crate::management::{
-    crates_io::CratesIOManager,
+    crates_io::{CratesIOManager, DependencyKind, manifest_dependencies, locked_dependencies},
+    lockfile::read_lockfile,
+    platform::Target,
+    cvss::Severity,
+    reachability::SourceIndex,
     security::SecurityDatabase,
+    toolchain,
+    workspace,
 };
 
-pub fn parse_args(manifest: Option<&str>, recursion: usize, updates: bool) {
+pub struct Arguments<'a> {
+    pub manifest: Option<&'a str>,
+    pub recursion: usize,
+    pub updates: bool,
+    pub db_max_age: u64,
+    pub offline: bool,
+    pub advisory_db: Option<&'a str>,
+    pub deny_informational: Vec<String>,
+    pub show_withdrawn: bool,
+    pub target: Option<&'a str>,
+    pub min_severity: Severity,
+    pub config: Option<&'a str>,
+    pub osv: Vec<String>,
+    pub db_url: Option<&'a str>,
+    pub db_sha256: Option<&'a str>,
+    pub db_checksum_url: Option<&'a str>,
+    pub cache_dir: Option<&'a str>,
+    pub details: bool,
+    pub lockfile: Option<&'a str>,
+    pub lockfile_only: bool,
+    pub kinds: Vec<DependencyKind>,
+}
+
+pub enum AdvisoryQuery<'a> {
+    Show(&'a str),
+    Search(&'a str),
+    List(&'a str),
+    Check(Vec<&'a str>),
+}
+
+pub fn parse_args(args: Arguments) {
     let visual_manager: OutputManager = OutputManager::new(0, 112);
-    let crate_mgr = CratesIOManager::new();
+    let mut crate_mgr = CratesIOManager::new();
+    let project_root = project_root(&args);
+    crate_mgr.sources = SourceIndex::new(project_root);
+    crate_mgr.details = args.details;
+    crate_mgr.lockfile = read_locked_versions(&args, &visual_manager);
+    crate_mgr.kinds = args.kinds.clone();
+    crate_mgr.target = args.target.map(Target::from_triple);
 
-    if updates {
+    let config = read_settings(&args, &visual_manager);
+
+    if args.updates && !args.offline {
         crate_mgr.check_self_update(&visual_manager);
     }
 
-    let mut advisory_db = SecurityDatabase::new();
-    let update_result = advisory_db.update();
-    if update_result.is_ok() {
-        let fetch_result = if let Some(manpath) = manifest {
-            crate_mgr.fetch_dependencies(manpath, &visual_manager, &advisory_db, recursion)
-        } else {
-            crate_mgr.fetch_dependencies("Cargo.toml", &visual_manager, &advisory_db, recursion)
-        };
-        if let Ok((good, bad, insecure, warn)) = fetch_result {
+    let advisory_db = open_database(&args, config, &visual_manager);
+
+    let fetch_result = if args.lockfile_only {
+        crate_mgr.check_lockfile(&visual_manager, &advisory_db)
+    } else if let Some(manpath) = args.manifest {
+        crate_mgr.fetch_dependencies(manpath, &visual_manager, &advisory_db, args.recursion)
+    } else {
+        crate_mgr.fetch_dependencies("Cargo.toml", &visual_manager, &advisory_db, args.recursion)
+    };
+    match fetch_result {
+        Ok((good, bad, insecure, warn, informational)) => {
             visual_manager::render(DisplayLine::new_guide());
             visual_manager::render(DisplayLine::new_footer());
             visual_manager::render(DisplayLine::new_guide());
-            visual_manager::render(DisplayLine::new_footer_content(good, bad, insecure, warn));
+            visual_manager::render(DisplayLine::new_footer_content(good, bad, insecure, warn, informational));
             visual_manager::render(DisplayLine::new_table_end());
-        } else {}
-    } else {
-        visual_manager.error(update_result.unwrap_err())
+
+            // A lockfile checked on its own, such as from a release tarball, says nothing about the toolchain it is built with
+            let toolchain_insecure = if args.lockfile_only { 0 } else { check_toolchain(project_root, &advisory_db, &visual_manager) };
+
+            if insecure > 0 || toolchain_insecure > 0 {
+                exit(1);
+            }
+        }
+        Err(error) => visual_manager.error(error),
+    }
+}
+
+/// Renders the "Toolchain" section, listing the advisories against the local Rust version,
+/// returns how many of them are vulnerabilities rather than informational
+fn check_toolchain(project_root: &Path, advisory_db: &SecurityDatabase, visual_manager: &OutputManager) -> usize {
+    let version = match toolchain::local_version(project_root) {
+        Some(version) => version,
+        None => return 0,
+    };
+    let affecting = toolchain::affecting_advisories(advisory_db, &version);
+
+    visual_manager::render(DisplayLine::new_title(format!("Toolchain: Rust {}", version).as_str()));
+    visual_manager::render(DisplayLine::new_guide());
+    if affecting.is_empty() {
+        visual_manager::render(DisplayLine::new_note("No advisories affect this toolchain", "\x1b[32m"));
+    }
+    let mut insecure = 0;
+    for (component, advisory) in affecting.iter() {
+        let id = advisory.advisory.clone().and_then(|details| details.id).unwrap_or_default();
+        let title = advisory_title(advisory).unwrap_or_default();
+        let color = if advisory_db.is_vulnerability(advisory) {
+            insecure += 1;
+            "\x1b[31m"
+        } else {
+            "\x1b[33m"
+        };
+        visual_manager::render(DisplayLine::new_note(format!("{} ({}): {}", id, component, title).as_str(), color));
     }
+    visual_manager::render(DisplayLine::new_table_end());
+
+    insecure
+}
+
+/// Looks up advisories in the database without checking a manifest
+pub fn advisory_command(args: Arguments, query: AdvisoryQuery) {
+    let visual_manager: OutputManager = OutputManager::new(0, 112);
+    let config = read_settings(&args, &visual_manager);
+    let advisory_db = open_database(&args, config, &visual_manager);
+
+    match query {
+        AdvisoryQuery::Show(id) => {
+            if let Some(advisory) = advisory_db.find(id) {
+                visual_manager.render_advisory(&advisory);
+            } else {
+                println!("No advisory found with the id {}", id);
+                exit(1);
+            }
+        }
+        AdvisoryQuery::Search(keyword) => {
+            let found = advisory_db.search(keyword);
+
+            if found.is_empty() {
+                println!("No advisories found matching {}", keyword);
+            }
+            for advisory in found.iter() {
+                visual_manager.render_advisory_summary(advisory);
+            }
+        }
+        AdvisoryQuery::List(crate_name) => {
+            let found = advisory_db.advisories.get(crate_name).cloned().unwrap_or_default();
+
+            if found.is_empty() {
+                println!("No advisories found for {}", crate_name);
+            }
+            for advisory in found.iter() {
+                visual_manager.render_advisory_summary(advisory);
+            }
+        }
+        AdvisoryQuery::Check(ids) => {
+            let lockfile = read_locked_versions(&args, &visual_manager);
+            let mut dependencies = match manifest_dependencies(args.manifest.unwrap_or("Cargo.toml"), lockfile.as_ref()) {
+                Ok(dependencies) => dependencies,
+                Err(error) => visual_manager.error(error),
+            };
+            // Packages only depended on indirectly are affected just the same, so everything in the lockfile is checked too
+            for locked in lockfile.as_ref().map(locked_dependencies).unwrap_or_default() {
+                if !dependencies.iter().any(|dep| dep.name == locked.name && dep.version.semver == locked.version.semver) {
+                    dependencies.push(locked);
+                }
+            }
+            let (mut affected, mut missing) = (false, false);
+
+            for id in ids {
+                let found = advisory_db.find_all(id);
+
+                if found.is_empty() {
+                    missing = true;
+                    println!(" \x1b[90;1m{}\x1b[0m not found in the advisory database", id);
+                }
+                for advisory in found.iter() {
+                    affected |= visual_manager.render_advisory_check(id, advisory, &dependencies);
+                }
+            }
+
+            if affected {
+                exit(1);
+            } else if missing {
+                exit(2);
+            }
+        }
+    }
+}
+
+/// The directory holding the manifest, where the config file and sources are looked for
+fn project_root<'a>(args: &Arguments<'a>) -> &'a Path {
+    match Path::new(args.manifest.unwrap_or("Cargo.toml")).parent() {
+        Some(root) if !root.as_os_str().is_empty() => root,
+        _ => Path::new("."),
+    }
+}
+
+fn read_settings(args: &Arguments, visual_manager: &OutputManager) -> Config {
+    let config_path = args.config.map(|path| Path::new(path).to_path_buf()).unwrap_or(project_root(args).join("version-checker.toml"));
+
+    match read_config(config_path.as_path(), args.config.is_some()) {
+        Ok(config) => config,
+        Err(error) => visual_manager.error(error),
+    }
+}
+
+/// Sets up the advisory database from the arguments and config, then loads it, exiting if it can't be loaded
+fn open_database(args: &Arguments, config: Config, visual_manager: &OutputManager) -> SecurityDatabase {
+    let mut advisory_db = SecurityDatabase::new();
+    advisory_db.max_age = args.db_max_age;
+    advisory_db.offline = args.offline;
+    if let Some(url) = args.db_url {
+        advisory_db.url = url.to_string();
+    }
+    advisory_db.pinned_sha256 = args.db_sha256.map(|hash| hash.to_string());
+    advisory_db.checksum_url = args.db_checksum_url.map(|url| url.to_string());
+    advisory_db.cache_dir = args.cache_dir.map(|path| Path::new(path).to_path_buf());
+    advisory_db.denied_informational = args.deny_informational.clone();
+    advisory_db.show_withdrawn = args.show_withdrawn;
+    advisory_db.min_severity = args.min_severity;
+    advisory_db.ignored = config.ignore.unwrap_or_default();
+    // `--advisory-db` stands in for the RustSec download when the config lists no sources, otherwise it is read
+    // ahead of them as a source of its own, so the remote sources the config lists are still downloaded
+    advisory_db.sources = match (config.sources, args.advisory_db) {
+        (None, path) => vec![AdvisorySource {
+            path: path.map(|path| path.to_string()),
+            ..AdvisorySource::rustsec()
+        }],
+        (Some(mut sources), Some(path)) => {
+            sources.insert(0, AdvisorySource {
+                name: "advisory-db".to_string(),
+                path: Some(path.to_string()),
+                format: None,
+            });
+            sources
+        }
+        (Some(sources), None) => sources,
+    };
+    for path in args.osv.iter() {
+        advisory_db.sources.push(AdvisorySource {
+            name: "osv".to_string(),
+            path: Some(path.clone()),
+            format: Some("osv".to_string()),
+        });
+    }
+    if let Some(triple) = args.target {
+        advisory_db.set_target(triple);
+    }
+
+    let update_result = advisory_db.load();
+    if let Err(error) = update_result {
+        visual_manager.error(error);
+    } else if advisory_db.stale {
+        visual_manager.warn_database_age(advisory_db.age_days(), advisory_db.offline);
+    }
+
+    advisory_db
+}
+
+/// Reads the Cargo.lock beside the manifest, or at the root of the workspace the manifest belongs to, or the one given
+/// with `--lockfile`, a missing file is only an error when given explicitly
+fn read_locked_versions(args: &Arguments, visual_manager: &OutputManager) -> Option<Lockfile> {
+    let beside = project_root(args).join("Cargo.lock");
+    let lockfile_path = match args.lockfile {
+        Some(path) => Path::new(path).to_path_buf(),
+        None if beside.exists() => beside,
+        None => workspace::root_manifest(Path::new(args.manifest.unwrap_or("Cargo.toml")))
+            .and_then(|root| root.parent().map(|directory| directory.join("Cargo.lock")))
+            .unwrap_or(beside),
+    };
+
+    if !lockfile_path.exists() && args.lockfile.is_none() {
+        return None;
+    }
+
+    match read_lockfile(lockfile_path.as_path()) {
+        Ok(lockfile) => Some(lockfile),
+        Err(error) => visual_manager.error(error),
+    }
+}
+
+/// Reads version-checker.toml, a missing file is only an error when the path was given explicitly
+fn read_config(path: &Path, required: bool) -> Result<Config, VerificationError> {
+    if !path.exists() && !required {
+        return Ok(Config::default());
+    }
+
+    let invalid = |reason: String| VerificationError::new(Errors::ConfigInvalid(format!("{}: {}", path.display(), reason)));
+    let content = read_to_string(path).map_err(|error| invalid(error.to_string()))?;
+    let config: Config = toml::from_str(content.as_str()).map_err(|error| invalid(error.to_string()))?;
+
+    // Without a path the source would download the RustSec database instead of the format it names
+    if let Some(source) = config.sources.iter().flatten().find(|source| source.format.is_some() && source.path.is_none()) {
+        return Err(invalid(format!("the source {} has a format but no path", source.name)));
+    }
+
+    Ok(config)
 }
\ No newline at end of file
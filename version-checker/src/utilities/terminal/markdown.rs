@@ -0,0 +1,144 @@
+/// The opening paragraph of an advisory description, leaving out the `# ` title which is shown separately.
+/// Headings before the paragraph are kept, the paragraph ends at the first blank line after some text
+pub fn first_paragraph(body: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut has_text = false;
+
+    for line in body.lines() {
+        let line = line.trim_end();
+
+        if line.starts_with("# ") {
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            if has_text {
+                break;
+            }
+            continue;
+        }
+
+        if !line.starts_with('#') {
+            has_text = true;
+        }
+        lines.push(line.to_string());
+    }
+
+    lines
+}
+
+/// Renders markdown lines as terminal text, wrapped to the given width.
+/// Headings are made bold, list items get a bullet, code spans are coloured and links are reduced to their text
+pub fn render(lines: &[String], width: usize) -> Vec<String> {
+    let mut blocks: Vec<String> = vec![];
+    let mut continues = false;
+
+    for line in lines {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('#') {
+            blocks.push(format!("\x1b[1m{}\x1b[0m", inline(trimmed.trim_start_matches('#').trim())));
+            continues = false;
+        } else if let Some(item) = list_item(trimmed) {
+            blocks.push(format!("• {}", inline(item)));
+            continues = true;
+        } else if continues && !blocks.is_empty() {
+            let last = blocks.len() - 1;
+            blocks[last] = format!("{} {}", blocks[last], inline(trimmed));
+        } else {
+            blocks.push(inline(trimmed));
+            continues = true;
+        }
+    }
+
+    blocks.iter().flat_map(|block| wrap(block, width)).collect()
+}
+
+/// How many columns a line takes up once its escape codes are left out
+pub fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut escaped = false;
+
+    for character in text.chars() {
+        if escaped {
+            escaped = character != 'm';
+        } else if character == '\x1b' {
+            escaped = true;
+        } else {
+            width += 1;
+        }
+    }
+
+    width
+}
+
+fn list_item(line: &str) -> Option<&str> {
+    if line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ") {
+        return Some(line[2..].trim());
+    }
+
+    let digits = line.chars().take_while(|character| character.is_ascii_digit()).count();
+    if digits > 0 && line[digits..].starts_with(". ") {
+        return Some(line[digits + 2..].trim());
+    }
+
+    None
+}
+
+fn inline(text: &str) -> String {
+    let mut rendered = String::new();
+    let mut rest = text;
+
+    while let Some(character) = rest.chars().next() {
+        if character == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                rendered = format!("{}\x1b[36m{}\x1b[0m", rendered, &rest[1..end + 1]);
+                rest = &rest[end + 2..];
+                continue;
+            }
+        } else if rest.starts_with("**") {
+            if let Some(end) = rest[2..].find("**") {
+                rendered = format!("{}\x1b[1m{}\x1b[0m", rendered, &rest[2..end + 2]);
+                rest = &rest[end + 4..];
+                continue;
+            }
+        } else if character == '[' {
+            if let Some(close) = rest.find(']').filter(|close| rest[close + 1..].starts_with('(')) {
+                if let Some(end) = rest[close + 2..].find(')') {
+                    rendered = format!("{}{}", rendered, &rest[1..close]);
+                    rest = &rest[close + 2 + end + 1..];
+                    continue;
+                }
+            }
+        }
+
+        rendered.push(character);
+        rest = &rest[character.len_utf8()..];
+    }
+
+    rendered
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        if !current.is_empty() && visible_width(current.as_str()) + 1 + visible_width(word) > width {
+            lines.push(current);
+            current = String::new();
+        }
+
+        if current.is_empty() {
+            current = word.to_string();
+        } else {
+            current = format!("{} {}", current, word);
+        }
+    }
+
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    lines
+}
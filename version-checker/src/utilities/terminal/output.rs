@@ -1,7 +1,10 @@
 use crate::utilities::terminal::output::OutputDisplayMode::{Table, Tree};
+use crate::utilities::terminal::markdown::visible_width;
 use crate::utilities::errors::VerificationError;
 use std::process::exit;
-use crate::management::crates_io::{Dependency, Version};
+use crate::management::crates_io::{Dependency, Version, AdvisoryCount, is_affected};
+use crate::management::cvss::Cvss;
+use crate::utilities::serial::security::ParentalAdvisory;
 use crate::VERSION;
 
 #[derive(Debug, Clone)]
@@ -12,6 +15,7 @@ pub enum OutputDisplayType {
     Title,
     Header,
     Guide,
+    Note,
     End,
 }
 
@@ -95,6 +99,93 @@ impl OutputManager {
         println!("╝\x1b[0m");
     }
 
+    /// Warns that an out of date copy of the advisory database is being used, either because refreshing it failed
+    /// or because the run is offline and doesn't try to
+    pub fn warn_database_age(&self, days: u64, offline: bool) {
+        if offline {
+            println!("\x1b[33;1mWarning:\x1b[0;33m running offline with a cached copy of the advisory database, the database is {} days old\x1b[0m", days);
+        } else {
+            println!("\x1b[33;1mWarning:\x1b[0;33m unable to refresh the advisory database, using a cached copy, the database is {} days old\x1b[0m", days);
+        }
+        println!();
+    }
+
+    /// One line per advisory, used when listing or searching the database
+    pub fn render_advisory_summary(&self, advisory: &ParentalAdvisory) {
+        let details = advisory.advisory.clone().unwrap_or_default();
+
+        println!(" \x1b[36;1m{}\x1b[0m \x1b[90m{}\x1b[0m {}: {}",
+                 details.id.unwrap_or_default(),
+                 details.date.unwrap_or_default(),
+                 details.package.unwrap_or_default(),
+                 advisory_title(advisory).unwrap_or_default());
+    }
+
+    /// Every field of an advisory followed by its description
+    pub fn render_advisory(&self, advisory: &ParentalAdvisory) {
+        let details = advisory.advisory.clone().unwrap_or_default();
+        let versions = advisory.versions.clone();
+
+        println!(" \x1b[36;1m{}\x1b[0m {}", details.id.clone().unwrap_or_default(), advisory_title(advisory).unwrap_or_default());
+        println!();
+        render_field("Package", details.package);
+        render_field("Date", details.date);
+        render_field("Aliases", details.aliases.map(|aliases| aliases.join(", ")));
+        render_field("Categories", details.categories.map(|categories| categories.join(", ")));
+        render_field("Keywords", details.keywords.map(|keywords| keywords.join(", ")));
+        render_field("CVSS", Cvss::of(advisory).map(|cvss| format!("{:.1} ({})", cvss.score, cvss.severity)));
+        render_field("Patched", versions.clone().and_then(|versions| versions.patched).map(|patched| patched.join(", ")));
+        render_field("Unaffected", versions.and_then(|versions| versions.unaffected).map(|unaffected| unaffected.join(", ")));
+        render_field("Informational", details.informational);
+        render_field("Withdrawn", details.withdrawn);
+        render_field("URL", details.url);
+        render_field("Source", advisory.source.clone());
+
+        if let Some(body) = advisory.body.clone() {
+            println!();
+            for line in body.trim().lines() {
+                println!(" {}", line);
+            }
+        }
+    }
+
+    /// Whether the dependencies are affected by an advisory looked up by id or alias, returns true when any are.
+    /// Dependencies whose version can't be read, such as git dependencies, are reported without counting as affected
+    pub fn render_advisory_check(&self, id: &str, advisory: &ParentalAdvisory, dependencies: &[Dependency]) -> bool {
+        let details = advisory.advisory.clone().unwrap_or_default();
+        let package = details.package.unwrap_or_default();
+        let versions = advisory.versions.clone();
+        let patched = versions.clone().and_then(|versions| versions.patched).unwrap_or_default();
+        let unaffected = versions.and_then(|versions| versions.unaffected).unwrap_or_default();
+        let mut affected = false;
+
+        let resolved = match details.id {
+            Some(advisory_id) if advisory_id != id => format!("{} ({})", id, advisory_id),
+            _ => id.to_string(),
+        };
+
+        let used: Vec<&Dependency> = dependencies.iter().filter(|dep| dep.name == package).collect();
+        if used.is_empty() {
+            println!(" \x1b[32m{}\x1b[0m {} is not a dependency", resolved, package);
+        }
+
+        for dep in used {
+            if dep.version.semver.is_none() {
+                println!(" \x1b[33m{}\x1b[0m {} {} version unknown", resolved, package, dep.version);
+            } else if is_affected(advisory, &dep.version) {
+                affected = true;
+                println!(" \x1b[31;1m{}\x1b[0m {} {} is affected", resolved, package, dep.version);
+            } else {
+                println!(" \x1b[32m{}\x1b[0m {} {} is not affected", resolved, package, dep.version);
+            }
+        }
+
+        render_field("Patched", Some(if patched.is_empty() { "no patched versions".to_string() } else { patched.join(", ") }));
+        render_field("Unaffected", if unaffected.is_empty() { None } else { Some(unaffected.join(", ")) });
+
+        affected
+    }
+
     pub fn render(content: DisplayLine) {
 
     }
@@ -214,7 +305,7 @@ impl OutputManager {
             OutputDisplayType::Guide => {
                 print!(" \x1b[90;1m╟");
                 for index in 0..self.display_width - 2 {
-                    if index == 13 || index == 62 || index == 86 {
+                    if index == 18 || index == 67 || index == 91 {
                         print!("┼");
                     } else {
                         print!("─");
@@ -222,6 +313,15 @@ impl OutputManager {
                 }
                 println!("╢\x1b[0m");
             }
+            OutputDisplayType::Note => {
+                let mut text = content.cells[0].text.clone();
+
+                while visible_width(text.as_str()) < self.display_width - 4 {
+                    text = format!("{} ", text);
+                }
+
+                println!(" \x1b[90;1m║\x1b[0m {}{}\x1b[0m \x1b[90;1m║\x1b[0m", content.cells[0].color, text);
+            }
             OutputDisplayType::Header => {}
             OutputDisplayType::End => {
                 let text = format!("Kalavar Version Checker v{}", VERSION);
@@ -252,8 +352,8 @@ impl OutputManager {
         println!("{:?}", content);
     }
 
-    pub fn error(&self, content: VerificationError) {
-        println!("{:?}", content);
+    pub fn error(&self, content: VerificationError) -> ! {
+        println!("\x1b[31;1mError:\x1b[0;31m {}\x1b[0m", content);
         exit(1)
     }
 }
@@ -285,56 +385,79 @@ impl DisplayLine {
         }
     }
 
-    pub fn new_crate(dep: Dependency, advisories: &u16) -> DisplayLine {
+    pub fn new_note(text: &str, color: &str) -> DisplayLine {
+        DisplayLine {
+            display_type: OutputDisplayType::Note,
+            cells: vec![DisplayCell {
+                text: format!("  {}", text),
+                width: 0,
+                color: color.to_string(),
+            }],
+        }
+    }
+
+    /// A heading above the dependencies of one kind, such as the dev-dependencies
+    pub fn new_group(text: &str) -> DisplayLine {
+        DisplayLine {
+            display_type: OutputDisplayType::Note,
+            cells: vec![DisplayCell {
+                text: text.to_string(),
+                width: 0,
+                color: "\x1b[36;1m".to_string(),
+            }],
+        }
+    }
+
+    pub fn new_crate(dep: Dependency, advisories: &AdvisoryCount) -> DisplayLine {
         DisplayLine {
             display_type: OutputDisplayType::Entry,
             cells: vec![
                 DisplayCell {
-                    text: format!("{}", advisories),
-                    width: 11,
+                    text: advisory_text(advisories),
+                    width: 16,
                     color: "\x1b[36m".to_string(),
                 },
                 DisplayCell {
-                    text: dep.name,
+                    text: name_text(&dep),
                     width: 50,
                     color: "\x1b[36m".to_string(),
                 },
                 DisplayCell {
-                    text: dep.version.to_string(),
+                    text: version_text(&dep),
                     width: 25,
                     color: "\x1b[36m".to_string(),
                 },
                 DisplayCell {
                     text: dep.remote.to_string(),
-                    width: 25,
+                    width: 20,
                     color: "\x1b[36m".to_string(),
                 }
             ],
         }
     }
 
-    pub fn new_crate_dep(dep: Dependency, advisories: &u16, indenter: &str) -> DisplayLine {
+    pub fn new_crate_dep(dep: Dependency, advisories: &AdvisoryCount, indenter: &str) -> DisplayLine {
         DisplayLine {
             display_type: OutputDisplayType::DepEntry,
             cells: vec![
                 DisplayCell {
-                    text: format!("{}", advisories),
-                    width: 11,
+                    text: advisory_text(advisories),
+                    width: 16,
                     color: "\x1b[36m".to_string(),
                 },
                 DisplayCell {
-                    text: format!("{} {}", indenter, dep.name),
+                    text: format!("{} {}", indenter, name_text(&dep)),
                     width: 50,
                     color: "\x1b[36m".to_string(),
                 },
                 DisplayCell {
-                    text: dep.version.to_string(),
+                    text: version_text(&dep),
                     width: 25,
                     color: "\x1b[36m".to_string(),
                 },
                 DisplayCell {
                     text: dep.remote.to_string(),
-                    width: 25,
+                    width: 20,
                     color: "\x1b[36m".to_string(),
                 }
             ],
@@ -347,7 +470,7 @@ impl DisplayLine {
             cells: vec![
                 DisplayCell {
                     text: "Advisories".to_string(),
-                    width: 11,
+                    width: 16,
                     color: "\x1b[36m".to_string(),
                 },
                 DisplayCell {
@@ -362,7 +485,7 @@ impl DisplayLine {
                 },
                 DisplayCell {
                     text: "Latest".to_string(),
-                    width: 25,
+                    width: 20,
                     color: "\x1b[36m".to_string(),
                 }
             ],
@@ -375,7 +498,7 @@ impl DisplayLine {
             cells: vec![
                 DisplayCell {
                     text: "Advisories".to_string(),
-                    width: 11,
+                    width: 16,
                     color: "\x1b[36m".to_string(),
                 },
                 DisplayCell {
@@ -390,20 +513,30 @@ impl DisplayLine {
                 },
                 DisplayCell {
                     text: "Out Of Date".to_string(),
-                    width: 25,
+                    width: 20,
                     color: "\x1b[36m".to_string(),
                 }
             ],
         }
     }
 
-    pub fn new_footer_content(utd: u16, ood: u16, advisories: u16, warn: u16) -> DisplayLine {
+    pub fn new_footer_content(utd: u16, ood: u16, advisories: u16, warn: u16, informational: u16) -> DisplayLine {
         let mut d = DisplayLine {
             display_type: OutputDisplayType::Entry,
             cells: vec![
                 DisplayCell {
-                    text: format!("{}", advisories),
-                    width: 11,
+                    text: advisory_text(&AdvisoryCount {
+                        vulnerabilities: advisories,
+                        informational,
+                        withdrawn: 0,
+                        filtered: 0,
+                        below: 0,
+                        score: None,
+                        matched: vec![],
+                        ignored: vec![],
+                        withdrawals: vec![],
+                    }),
+                    width: 16,
                     color: "\x1b[36m".to_string(),
                 },
                 DisplayCell {
@@ -418,7 +551,7 @@ impl DisplayLine {
                 },
                 DisplayCell {
                     text: format!("{}", ood),
-                    width: 25,
+                    width: 20,
                     color: "\x1b[36m".to_string(),
                 }
             ],
@@ -426,6 +559,8 @@ impl DisplayLine {
 
         if advisories > 0 {
             d.cells[0].color = "\x1b[31m".to_string();
+        } else if informational > 0 {
+            d.cells[0].color = "\x1b[33m".to_string();
         } else {
             d.cells[0].color = "\x1b[32m".to_string();
         }
@@ -440,4 +575,88 @@ impl DisplayLine {
 
         d
     }
-}
\ No newline at end of file
+
+    /// The header of the workspace summary, which has one row per member
+    pub fn new_summary_header() -> DisplayLine {
+        let mut d = DisplayLine::new_footer();
+        d.cells[1].text = "Member".to_string();
+        d
+    }
+
+    /// The totals of one workspace member, laid out like the footer of its own report
+    pub fn new_summary_content(member: &str, utd: u16, ood: u16, advisories: u16, warn: u16, informational: u16) -> DisplayLine {
+        let mut d = DisplayLine::new_footer_content(utd, ood, advisories, warn, informational);
+        d.cells[1].text = format!("{} ({})", member, utd + ood + warn);
+        d
+    }
+}
+
+/// The highest CVSS score is shown beside the advisory count, followed by informational, withdrawn, other platform
+/// and below minimum severity advisories as `+Ni`, `+Nw`, `+Np` and `+Nb`. When those don't fit in the 16 column
+/// cell only their total is shown, as `+N`
+fn advisory_text(advisories: &AdvisoryCount) -> String {
+    let mut text = format!("{}", advisories.vulnerabilities);
+
+    if let Some(score) = advisories.score {
+        text = format!("{} ({:.1})", text, score);
+    }
+    let counted = text.clone();
+
+    if advisories.informational > 0 {
+        text = format!("{} +{}i", text, advisories.informational);
+    }
+
+    if advisories.withdrawn > 0 {
+        text = format!("{} +{}w", text, advisories.withdrawn);
+    }
+
+    if advisories.filtered > 0 {
+        text = format!("{} +{}p", text, advisories.filtered);
+    }
+
+    if advisories.below > 0 {
+        text = format!("{} +{}b", text, advisories.below);
+    }
+
+    if text.len() > 16 {
+        text = format!("{} +{}", counted, advisories.informational + advisories.withdrawn + advisories.filtered + advisories.below);
+    }
+
+    text
+}
+
+/// The name of a dependency, marked when it is optional. The target of a target specific dependency is shown in a note
+/// below its row instead, `cfg(...)` expressions are often longer than the column
+fn name_text(dep: &Dependency) -> String {
+    let mut text = dep.name.clone();
+
+    if dep.optional {
+        text = format!("{} (optional)", text);
+    }
+
+    text
+}
+
+/// The version of a dependency, followed by the requirement from the manifest when the version was read from Cargo.lock
+fn version_text(dep: &Dependency) -> String {
+    let exact = format!("={}", dep.version);
+
+    match dep.requirement.as_ref() {
+        Some(requirement) if !requirement.is_empty() && *requirement != dep.version.to_string() && *requirement != exact => format!("{} ({})", dep.version, requirement),
+        _ => dep.version.to_string(),
+    }
+}
+
+fn render_field(name: &str, value: Option<String>) {
+    if let Some(value) = value {
+        println!(" \x1b[90;1m{:<14}\x1b[0m {}", name, value);
+    }
+}
+
+/// The first markdown heading of an advisory's description
+pub fn advisory_title(advisory: &ParentalAdvisory) -> Option<String> {
+    advisory.body.clone()?
+        .lines()
+        .find(|line| line.starts_with("# "))
+        .map(|line| line[2..].trim().to_string())
+}